@@ -0,0 +1,96 @@
+use crate::response::FuzzResponse;
+
+use rand::{Rng, distr::Alphanumeric};
+use serde::Serialize;
+use sender::Sender;
+
+/// Lengths of the random payloads sent to learn the baseline. They differ so that
+/// a page reflecting the payload shows up as an unstable size.
+const PROBE_LENGTHS: [usize; 3] = [8, 16, 32];
+
+/// What the target answers for payloads that cannot exist.
+/// A field is only set when every probe agreed on its value.
+#[derive(Clone, Serialize)]
+pub struct Calibration {
+    /// URL template of the calibrated target.
+    pub target: String,
+    pub status: Option<u16>,
+    pub size: Option<u64>,
+    pub words: Option<usize>,
+    pub lines: Option<usize>,
+    #[serde(skip)]
+    pub samples: Vec<FuzzResponse>,
+}
+
+impl Calibration {
+    pub fn run(sender: &Sender, url_template: &str) -> Self {
+        let samples: Vec<FuzzResponse> = PROBE_LENGTHS
+            .iter()
+            .filter_map(|&len| {
+                let payload = random_payload(len);
+                let url = url_template.replace("{}", &payload);
+                let exchange = sender.send(&url).ok()?;
+                Some(FuzzResponse::new(exchange, &url, &payload, url_template))
+            })
+            .collect();
+
+        Self::from_samples(url_template, samples)
+    }
+
+    /// Calibration learned from `samples`, the responses to random payloads.
+    pub fn from_samples(url_template: &str, samples: Vec<FuzzResponse>) -> Self {
+        Self {
+            target: url_template.to_string(),
+            status: stable(&samples, |r| r.status),
+            size: stable(&samples, |r| r.size),
+            words: stable(&samples, |r| r.words),
+            lines: stable(&samples, |r| r.lines),
+            samples,
+        }
+    }
+
+    /// Whether anything was learned that can tell a baseline response apart.
+    /// The status must be stable: without it, a real page with the same
+    /// number of lines or words as the baseline would be filtered.
+    pub fn is_usable(&self) -> bool {
+        self.status.is_some() && (self.size.is_some() || self.words.is_some() || self.lines.is_some())
+    }
+
+    pub fn describe(&self) -> String {
+        if !self.is_usable() {
+            return String::from("no stable baseline");
+        }
+
+        let mut parts = Vec::new();
+        if let Some(status) = self.status {
+            parts.push(format!("status {}", status));
+        }
+        if let Some(size) = self.size {
+            parts.push(format!("size {}", size));
+        }
+        if let Some(words) = self.words {
+            parts.push(format!("words {}", words));
+        }
+        if let Some(lines) = self.lines {
+            parts.push(format!("lines {}", lines));
+        }
+        parts.join(", ")
+    }
+}
+
+pub fn random_payload(len: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+fn stable<T: PartialEq + Copy>(samples: &[FuzzResponse], field: impl Fn(&FuzzResponse) -> T) -> Option<T> {
+    let first = field(samples.first()?);
+
+    match samples.iter().all(|sample| field(sample) == first) {
+        true => Some(first),
+        false => None,
+    }
+}
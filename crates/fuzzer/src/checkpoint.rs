@@ -0,0 +1,288 @@
+use crate::filter::dedupe::Original;
+use crate::response::FuzzResponse;
+use crate::scheduler::JobState;
+use crate::stats::Counters;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+/// File an interrupted scan is saved to when no checkpoint file was given.
+pub const DEFAULT_CHECKPOINT: &str = "rake.checkpoint.json";
+
+/// Time between two checkpoints of a running scan.
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+pub enum CheckpointError {
+    FileNotFound,
+    Invalid,
+    WriteFailed,
+    Mismatch,
+}
+
+impl CheckpointError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointError::FileNotFound => "Checkpoint file not found",
+            CheckpointError::Invalid => "Checkpoint file is not a valid rake checkpoint",
+            CheckpointError::WriteFailed => "Checkpoint file cannot be written",
+            CheckpointError::Mismatch => "Checkpoint was saved for other targets or another wordlist",
+        }
+    }
+}
+
+/// Everything needed to continue a scan where it stopped.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub targets: Vec<String>,
+    pub wordlist: String,
+    /// Jobs not finished yet. Empty once the scan completed.
+    pub jobs: Vec<JobState>,
+    /// Templates of every job queued so far, so recursion does not start them again.
+    pub queued: Vec<String>,
+    /// URLs of every link queued so far, when links are extracted.
+    #[serde(default)]
+    pub links: Vec<String>,
+    pub counters: Counters,
+    /// Responses to the random payloads each target was calibrated with, so that a resumed scan
+    /// filters the same responses out.
+    #[serde(default)]
+    pub calibrations: Vec<(String, Vec<FuzzResponse>)>,
+    /// Responses deduplication compares the next ones to.
+    #[serde(default)]
+    pub originals: Vec<Original>,
+    /// Number of results saved to the `ResultLog` of the checkpoint.
+    pub results: usize,
+}
+
+impl Checkpoint {
+    pub fn load(path: &str) -> Result<Self, CheckpointError> {
+        let file = File::open(path).map_err(|_| CheckpointError::FileNotFound)?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|_| CheckpointError::Invalid)
+    }
+
+    /// Writes next to `path` first, so that a scan killed while saving keeps its previous checkpoint.
+    pub fn save(&self, path: &str) -> Result<(), CheckpointError> {
+        let temporary = format!("{}.tmp", path);
+
+        let file = File::create(&temporary).map_err(|_| CheckpointError::WriteFailed)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, self).map_err(|_| CheckpointError::WriteFailed)?;
+        writer.flush().map_err(|_| CheckpointError::WriteFailed)?;
+        drop(writer);
+
+        fs::rename(&temporary, path).map_err(|_| CheckpointError::WriteFailed)
+    }
+
+    pub fn matches(&self, targets: &[String], wordlist: &str) -> bool {
+        self.targets == targets && self.wordlist == wordlist
+    }
+
+    /// `DEFAULT_CHECKPOINT`, or `rake.checkpoint.N.json` if it is taken,
+    /// so that a checkpoint the user did not name never replaces an earlier one.
+    pub fn default_path() -> String {
+        (1..)
+            .map(|n| match n {
+                1 => DEFAULT_CHECKPOINT.to_string(),
+                n => format!("rake.checkpoint.{}.json", n),
+            })
+            .find(|path| !Path::new(path).exists() && !Path::new(&ResultLog::path(path)).exists())
+            .unwrap()
+    }
+}
+
+/// Results of a checkpoint, one JSON line each in a file next to it,
+/// so that every save only appends the results found since the previous one.
+pub struct ResultLog {
+    file: File,
+    /// Results in the file, and its length once they were written.
+    count: usize,
+    length: u64,
+}
+
+impl ResultLog {
+    pub fn path(checkpoint: &str) -> String {
+        format!("{}.results", checkpoint)
+    }
+
+    /// Starts an empty log for `checkpoint`, replacing any previous one.
+    pub fn create(checkpoint: &str) -> Result<Self, CheckpointError> {
+        let file = File::create(Self::path(checkpoint)).map_err(|_| CheckpointError::WriteFailed)?;
+        Ok(Self {
+            file,
+            count: 0,
+            length: 0,
+        })
+    }
+
+    /// Reads the first `count` results of the log of `checkpoint`.
+    pub fn read(checkpoint: &str, count: usize) -> Result<Vec<FuzzResponse>, CheckpointError> {
+        Ok(Self::read_lines(checkpoint, count)?.0)
+    }
+
+    /// Opens the log of `checkpoint` to append to it, with the `count` results it was saved with.
+    /// Lines past them were written by a save that did not complete, and are dropped.
+    pub fn open(checkpoint: &str, count: usize) -> Result<Self, CheckpointError> {
+        let length = Self::read_lines(checkpoint, count)?.1;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(Self::path(checkpoint))
+            .map_err(|_| CheckpointError::WriteFailed)?;
+        file.set_len(length).map_err(|_| CheckpointError::WriteFailed)?;
+        file.seek(SeekFrom::End(0)).map_err(|_| CheckpointError::WriteFailed)?;
+
+        Ok(Self { file, count, length })
+    }
+
+    fn read_lines(checkpoint: &str, count: usize) -> Result<(Vec<FuzzResponse>, u64), CheckpointError> {
+        let file = match File::open(Self::path(checkpoint)) {
+            Ok(file) => file,
+            Err(_) if count == 0 => return Ok((Vec::new(), 0)),
+            Err(_) => return Err(CheckpointError::FileNotFound),
+        };
+        let mut reader = BufReader::new(file);
+        let mut results = Vec::with_capacity(count);
+        let mut length = 0;
+        let mut line = String::new();
+
+        while results.len() < count {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(|_| CheckpointError::Invalid)?;
+            if read == 0 || !line.ends_with('\n') {
+                return Err(CheckpointError::Invalid);
+            }
+            results.push(serde_json::from_str(&line).map_err(|_| CheckpointError::Invalid)?);
+            length += read as u64;
+        }
+
+        Ok((results, length))
+    }
+
+    /// Appends `results` and returns the number of results in the log.
+    /// On failure the log is left as it was, so the same results can be appended again.
+    pub fn append(&mut self, results: &[Arc<FuzzResponse>]) -> Result<usize, CheckpointError> {
+        let mut lines = Vec::new();
+        for response in results {
+            serde_json::to_writer(&mut lines, response).map_err(|_| CheckpointError::WriteFailed)?;
+            lines.push(b'\n');
+        }
+
+        if self.file.write_all(&lines).and_then(|_| self.file.flush()).is_err() {
+            let _ = self.file.set_len(self.length);
+            let _ = self.file.seek(SeekFrom::Start(self.length));
+            return Err(CheckpointError::WriteFailed);
+        }
+
+        self.count += results.len();
+        self.length += lines.len() as u64;
+        Ok(self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::dedupe::Deduplicator;
+    use crate::recursion::Job;
+
+    /// Checkpoint path in the temporary directory, unique to the test.
+    fn path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rake-{}-{}.json", std::process::id(), name));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn result(payload: &str) -> Arc<FuzzResponse> {
+        let url = format!("http://target/{}", payload);
+        Arc::new(FuzzResponse::fixture(200, &url, payload, "<p>found</p>"))
+    }
+
+    fn urls(results: &[FuzzResponse]) -> Vec<&str> {
+        results.iter().map(|response| response.url.as_str()).collect()
+    }
+
+    #[test]
+    fn saves_and_loads_the_scan_state() {
+        let path = path("state");
+        let deduplicator = Deduplicator::new();
+        deduplicator.is_duplicate(&result("backup"));
+
+        let checkpoint = Checkpoint {
+            targets: vec![String::from("http://target/{}")],
+            wordlist: String::from("words.txt"),
+            jobs: vec![JobState {
+                job: Job::new("http://target/{}"),
+                position: 42,
+                lines_read: 7,
+                pending: vec![String::from("admin")],
+            }],
+            queued: vec![String::from("http://target/admin/{}")],
+            links: Vec::new(),
+            counters: Counters::default(),
+            calibrations: vec![(String::from("http://target/{}"), vec![(*result("x1y2z3")).clone()])],
+            originals: deduplicator.snapshot(),
+            results: 3,
+        };
+        checkpoint.save(&path).ok().unwrap();
+        let loaded = Checkpoint::load(&path).ok().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.matches(&checkpoint.targets, "words.txt"));
+        assert!(!loaded.matches(&checkpoint.targets, "other.txt"));
+        assert_eq!(loaded.jobs[0].job, Job::new("http://target/{}"));
+        assert_eq!(loaded.jobs[0].completed(), 6);
+        assert_eq!(loaded.queued, checkpoint.queued);
+        assert_eq!(loaded.calibrations[0].1[0].url, "http://target/x1y2z3");
+        assert_eq!(loaded.results, 3);
+
+        // The restored originals still collapse the pages they were seen with.
+        let restored = Deduplicator::new();
+        restored.restore(loaded.originals);
+        assert!(restored.is_duplicate(&result("old")));
+        assert_eq!(restored.duplicates()[0].first_url, "http://target/backup");
+    }
+
+    #[test]
+    fn appends_results_to_the_log() {
+        let path = path("append");
+        let mut log = ResultLog::create(&path).ok().unwrap();
+        assert_eq!(log.append(&[result("a"), result("b")]).ok(), Some(2));
+        assert_eq!(log.append(&[]).ok(), Some(2));
+        assert_eq!(log.append(&[result("c")]).ok(), Some(3));
+
+        let results = ResultLog::read(&path, 3).ok().unwrap();
+        fs::remove_file(ResultLog::path(&path)).unwrap();
+        assert_eq!(urls(&results), ["http://target/a", "http://target/b", "http://target/c"]);
+    }
+
+    #[test]
+    fn drops_results_of_an_incomplete_save() {
+        let path = path("incomplete");
+        let mut log = ResultLog::create(&path).ok().unwrap();
+        log.append(&[result("a"), result("b")]).ok().unwrap();
+        // The checkpoint was saved with one result, and the last line was cut short.
+        let mut file = OpenOptions::new().append(true).open(ResultLog::path(&path)).unwrap();
+        file.write_all(b"{\"url\":").unwrap();
+
+        let mut log = ResultLog::open(&path, 1).ok().unwrap();
+        assert_eq!(log.append(&[result("c")]).ok(), Some(2));
+
+        let results = ResultLog::read(&path, 2).ok().unwrap();
+        assert!(ResultLog::read(&path, 3).is_err());
+        fs::remove_file(ResultLog::path(&path)).unwrap();
+        assert_eq!(urls(&results), ["http://target/a", "http://target/c"]);
+    }
+
+    #[test]
+    fn reads_a_missing_log_only_when_empty() {
+        let path = path("missing");
+        assert!(ResultLog::read(&path, 0).ok().unwrap().is_empty());
+        assert!(matches!(ResultLog::read(&path, 1), Err(CheckpointError::FileNotFound)));
+    }
+}
@@ -0,0 +1,329 @@
+use crate::DisplayArgs;
+use crate::filter::FilterStage;
+use crate::limits::{StopReason, StopSignal};
+use crate::output::OutputSink;
+use crate::rate::RateLimiter;
+use crate::scheduler::Scheduler;
+use crate::stats::Statistics;
+
+use colored::*;
+use crossbeam::channel::{self, Receiver};
+use crossbeam::select;
+use std::{
+    io::{self, BufRead, Write},
+    str::FromStr,
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+/// Most values a range such as `400-410` may expand to.
+const MAX_RANGE: u64 = 10_000;
+
+const HELP: &str = "\
+  fc, fs, fw <values>   add filtered codes, sizes or words, `-<value>` removes one
+  mc, ms, mw <values>   add matched codes, sizes or words, `-<value>` removes one
+  queue                 show the jobs not finished yet
+  stats                 show the counters so far
+  rate [n]              show or set the requests per second, 0 for no limit
+  stop                  end the scan and save a checkpoint
+  resume                resume the scan, as does an empty line";
+
+/// Holds the workers back while the console is open.
+pub struct Pause {
+    /// Read by the workers before every request: the lock is only taken while the scan is paused.
+    paused: AtomicBool,
+    lock: Mutex<()>,
+    changed: Condvar,
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub fn set(&self, paused: bool) {
+        let _lock = self.lock.lock().unwrap();
+        self.paused.store(paused, Ordering::SeqCst);
+        self.changed.notify_all();
+    }
+
+    /// Blocks while the scan is paused, unless `stopping` tells it gets stopped meanwhile.
+    pub fn wait(&self, stopping: impl Fn() -> bool) {
+        if !self.paused.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut lock = self.lock.lock().unwrap();
+        while self.paused.load(Ordering::SeqCst) && !stopping() {
+            lock = self.changed.wait_timeout(lock, Duration::from_millis(100)).unwrap().0;
+        }
+    }
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Step {
+    Continue,
+    Resume,
+}
+
+/// Interactive prompt of a running scan, opened by pressing Enter.
+/// Workers are paused while it is open.
+pub struct Console<'a> {
+    pub config: DisplayArgs,
+    pub filter: &'a FilterStage,
+    pub sinks: &'a [Mutex<Box<dyn OutputSink>>],
+    pub scheduler: &'a Scheduler,
+    pub statistics: &'a Statistics,
+    pub rate: &'a RateLimiter,
+    pub pause: &'a Pause,
+    pub stop: &'a StopSignal,
+    pub wordlist_size: usize,
+}
+
+impl Console<'_> {
+    /// Waits for Enter until `stopped` is disconnected, once every worker is done.
+    pub fn run(mut self, stopped: Receiver<()>) {
+        let lines = Self::read_lines();
+
+        loop {
+            select! {
+                recv(stopped) -> _ => return,
+                recv(lines) -> line => match line {
+                    Ok(_) => self.open(&lines, &stopped),
+                    Err(_) => return,
+                },
+            }
+        }
+    }
+
+    /// Stdin is read on a thread of its own, as reading cannot be interrupted when the scan ends.
+    fn read_lines() -> Receiver<String> {
+        let (sender, receiver) = channel::unbounded();
+
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    fn open(&mut self, lines: &Receiver<String>, stopped: &Receiver<()>) {
+        self.pause.set(true);
+        for sink in self.sinks {
+            sink.lock().unwrap().on_pause();
+        }
+
+        eprintln!("{}", "Scan paused. Type `help` for commands, an empty line resumes.".yellow());
+
+        loop {
+            eprint!("rake> ");
+            let _ = io::stderr().flush();
+
+            let line = select! {
+                recv(stopped) -> _ => break,
+                recv(lines) -> line => match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                },
+            };
+
+            match self.execute(line.trim()) {
+                Ok(Step::Continue) => {}
+                Ok(Step::Resume) => break,
+                Err(e) => eprintln!("{}", e.red()),
+            }
+        }
+
+        self.pause.set(false);
+        for sink in self.sinks {
+            sink.lock().unwrap().on_resume();
+        }
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Step, String> {
+        let (command, values) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "" | "resume" => return Ok(Step::Resume),
+            "help" => eprintln!("{}", HELP),
+            "queue" => self.queue(),
+            "stats" => self.stats(),
+            "rate" => self.rate(values)?,
+            "stop" => {
+                self.stop.stop(StopReason::Stopped);
+                return Ok(Step::Resume);
+            }
+            "fc" => edit(&mut self.config.filtered_code, values, parse_numbers)?,
+            "fs" => edit(&mut self.config.filtered_size, values, parse_numbers)?,
+            "fw" => edit(&mut self.config.filtered_word, values, parse_word)?,
+            "mc" => edit(&mut self.config.matched_code, values, parse_numbers)?,
+            "ms" => edit(&mut self.config.matched_size, values, parse_numbers)?,
+            "mw" => edit(&mut self.config.matched_word, values, parse_word)?,
+            _ => return Err(format!("Unknown command `{}`, type `help` for commands", command)),
+        }
+
+        if matches!(command, "fc" | "fs" | "fw" | "mc" | "ms" | "mw") {
+            self.filter.reload(&self.config);
+            for sink in self.sinks {
+                sink.lock().unwrap().on_update(&self.config);
+            }
+        }
+
+        Ok(Step::Continue)
+    }
+
+    fn queue(&self) {
+        let jobs = self.scheduler.snapshot();
+        if jobs.is_empty() {
+            eprintln!("  No job left");
+        }
+        for state in jobs {
+            eprintln!(
+                "  [{}/{}] {} {}",
+                state.completed(),
+                self.wordlist_size,
+                state.job.template,
+                format!("(depth {})", state.job.depth).dimmed()
+            );
+        }
+    }
+
+    fn stats(&self) {
+        let summary = self.statistics.summary();
+        eprintln!(
+            "  {} requests ({:.1} req/s), {} matched, {} filtered, {} errors",
+            summary.total_requests,
+            summary.requests_per_second,
+            summary.matched,
+            summary.filtered,
+            summary.errored
+        );
+    }
+
+    fn rate(&self, value: &str) -> Result<(), String> {
+        if !value.trim().is_empty() {
+            let rate = value.trim().parse().map_err(|_| format!("Invalid rate `{}`", value.trim()))?;
+            self.rate.set_rate(rate);
+        }
+
+        match self.rate.rate() {
+            0 => eprintln!("  No rate limit"),
+            rate => eprintln!("  {} requests per second", rate),
+        }
+        Ok(())
+    }
+}
+
+/// Adds the values of a command to `list`, or removes those starting with `-`.
+fn edit<T: PartialEq>(
+    list: &mut Vec<T>,
+    values: &str,
+    parse: fn(&str) -> Result<Vec<T>, String>,
+) -> Result<(), String> {
+    let tokens: Vec<&str> = values.split([' ', ',']).filter(|token| !token.is_empty()).collect();
+    if tokens.is_empty() {
+        return Err(String::from("Missing values"));
+    }
+
+    for token in tokens {
+        match token.strip_prefix('-') {
+            Some(token) => {
+                let removed = parse(token)?;
+                list.retain(|value| !removed.contains(value));
+            }
+            None => {
+                for value in parse(token)? {
+                    if !list.contains(&value) {
+                        list.push(value);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses a value or a range of values such as `400-410`, of at most `MAX_RANGE` values.
+fn parse_numbers<T: FromStr + TryFrom<u64>>(token: &str) -> Result<Vec<T>, String> {
+    let invalid = || format!("Invalid value `{}`", token);
+    let (start, end) = token.split_once('-').unwrap_or((token, token));
+    let start: u64 = start.parse().map_err(|_| invalid())?;
+    let end: u64 = end.parse().map_err(|_| invalid())?;
+
+    if start > end {
+        return Err(format!("Invalid range `{}`: its start is greater than its end", token));
+    }
+    if end - start >= MAX_RANGE {
+        return Err(format!("Range `{}` is too large, at most {} values are allowed", token, MAX_RANGE));
+    }
+
+    (start..=end).map(|value| T::try_from(value).map_err(|_| invalid())).collect()
+}
+
+fn parse_word(token: &str) -> Result<Vec<String>, String> {
+    Ok(vec![token.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_and_ranges() {
+        assert_eq!(parse_numbers::<u16>("404"), Ok(vec![404]));
+        assert_eq!(parse_numbers::<u16>("400-403"), Ok(vec![400, 401, 402, 403]));
+        assert_eq!(parse_numbers::<u64>("0-0"), Ok(vec![0]));
+    }
+
+    #[test]
+    fn rejects_invalid_and_oversized_ranges() {
+        assert!(parse_numbers::<u16>("4o4").is_err());
+        assert!(parse_numbers::<u16>("410-400").is_err());
+        assert!(parse_numbers::<u16>("70000").is_err());
+        assert!(parse_numbers::<u64>("0-18446744073709551615").is_err());
+        assert_eq!(parse_numbers::<u64>("1-10000").map(|values| values.len()), Ok(10_000));
+        assert!(parse_numbers::<u64>("1-10001").is_err());
+    }
+
+    #[test]
+    fn adds_and_removes_values() {
+        let mut codes: Vec<u16> = vec![404];
+
+        edit(&mut codes, "403, 500-502 404", parse_numbers).unwrap();
+        assert_eq!(codes, vec![404, 403, 500, 501, 502]);
+
+        edit(&mut codes, "-404 -501-502", parse_numbers).unwrap();
+        assert_eq!(codes, vec![403, 500]);
+
+        assert!(edit(&mut codes, " ", parse_numbers).is_err());
+        assert!(edit(&mut codes, "403 oops", parse_numbers).is_err());
+    }
+
+    #[test]
+    fn keeps_words_as_given() {
+        let mut words = Vec::new();
+
+        edit(&mut words, "denied,not-found", parse_word).unwrap();
+        assert_eq!(words, vec![String::from("denied"), String::from("not-found")]);
+
+        edit(&mut words, "-denied", parse_word).unwrap();
+        assert_eq!(words, vec![String::from("not-found")]);
+    }
+}
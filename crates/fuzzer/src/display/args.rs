@@ -1,7 +1,21 @@
+use crate::calibration::Calibration;
+use crate::expression::Expression;
+use crate::filter::FilterMode;
+use crate::limits::Limits;
+use crate::order::ResultOrder;
+use crate::output::{Column, OutputTarget};
+use crate::recursion::RecursionStrategy;
+use crate::seeds::Seeds;
+use crate::template::Template;
+
+use serde::Serialize;
+
+#[derive(Clone, Serialize)]
 pub struct DisplayArgs {
-    pub url: String,
+    pub targets: Vec<String>,
     pub wordlist: String,
     pub threads: u8,
+    pub rate: u32,
     pub timeout: u64,
     pub user_agent: String,
     pub filtered_code: Vec<u16>,
@@ -10,5 +24,73 @@ pub struct DisplayArgs {
     pub matched_code: Vec<u16>,
     pub matched_size: Vec<u64>,
     pub matched_word: Vec<String>,
+    pub expression: Option<Expression>,
+    pub match_mode: FilterMode,
+    pub filter_mode: FilterMode,
+    pub calibrations: Vec<Calibration>,
+    pub seeds: Vec<Seeds>,
+    pub similarity: Option<u8>,
+    pub dedupe: bool,
+    pub recursion_depth: usize,
+    pub recursion_status: Vec<u16>,
+    pub recursion_strategy: RecursionStrategy,
+    pub extract_links: bool,
+    pub order: ResultOrder,
+    pub limits: Limits,
+    pub outputs: Vec<OutputTarget>,
+    pub columns: Vec<Column>,
+    pub output_dir: Option<String>,
+    pub checkpoint: Option<String>,
+    pub resume: Option<String>,
+    pub interactive: bool,
+    pub template: Option<Template>,
+    pub silent: bool,
+    pub verbose: bool,
+    pub no_color: bool,
     pub method: String,
 }
+
+#[cfg(test)]
+impl DisplayArgs {
+    /// Arguments of a scan of `http://target/{}` without any matcher or filter.
+    pub fn fixture() -> Self {
+        Self {
+            targets: vec![String::from("http://target/{}")],
+            wordlist: String::from("words.txt"),
+            threads: 1,
+            rate: 0,
+            timeout: 5000,
+            user_agent: String::from("rake/1.0"),
+            filtered_code: Vec::new(),
+            filtered_size: Vec::new(),
+            filtered_word: Vec::new(),
+            matched_code: Vec::new(),
+            matched_size: Vec::new(),
+            matched_word: Vec::new(),
+            expression: None,
+            match_mode: FilterMode::Or,
+            filter_mode: FilterMode::Or,
+            calibrations: Vec::new(),
+            seeds: Vec::new(),
+            similarity: None,
+            dedupe: false,
+            recursion_depth: 0,
+            recursion_status: Vec::new(),
+            recursion_strategy: RecursionStrategy::Default,
+            extract_links: false,
+            order: ResultOrder::default(),
+            limits: Limits::default(),
+            outputs: Vec::new(),
+            columns: Column::DEFAULT.to_vec(),
+            output_dir: None,
+            checkpoint: None,
+            resume: None,
+            interactive: false,
+            template: None,
+            silent: false,
+            verbose: false,
+            no_color: true,
+            method: String::from("GET"),
+        }
+    }
+}
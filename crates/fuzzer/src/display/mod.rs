@@ -1,129 +1,255 @@
-pub mod filter;
 pub mod args;
 
-use crate::display::filter::{ContentSizeFilter, ResponseFilter, StatusCodeFilter};
+use crate::output::{OutputFormat, OutputSink};
+use crate::recursion::Job;
+use crate::response::FuzzResponse;
+use crate::stats::{ErrorKind, ScanSummary};
+use crate::template::Template;
 use crate::DisplayArgs;
 
-use std::{time::Duration, ops::Add, fmt};
-use filter::WordFilter;
-use reqwest::blocking::Response;
-use indicatif::{ProgressBar, ProgressStyle};
+use std::{collections::HashMap, ops::Add, fmt, io::{self, IsTerminal}, sync::Arc};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use colored::*;
 
+/// Formats a list of numbers, collapsing runs of 6 or more into ranges.
+fn range_formatted<T>(range: &[T]) -> Vec<String>
+where
+    T: Copy + PartialOrd + Add<Output = T> + fmt::Display + Ord + TryFrom<u64> + Into<u64>,
+{
+    if range.is_empty() {
+        return vec![];
+    }
+
+    let mut sorted = range.to_owned();
+    sorted.sort();
+
+    let mut result = Vec::new();
+    let mut start = sorted[0];
+    let mut prev = sorted[0];
+    let mut count = 1;
+
+    for &num in sorted.iter().skip(1) {
+        if num.into() == prev.into() + 1 {
+            count += 1;
+        } else {
+            if count >= 6 {
+                result.push(format!("{}-{}", start, prev));
+            } else {
+                for n in start.into()..=prev.into() {
+                    result.push(n.to_string());
+                }
+            }
+            start = num;
+            count = 1;
+        }
+        prev = num;
+    }
+
+    if count >= 6 {
+        result.push(format!("{}-{}", start, prev));
+    } else {
+        for n in start.into()..=prev.into() {
+            result.push(n.to_string());
+        }
+    }
+
+    result
+}
+
+/// Renders the scan on the terminal: banner, result lines, progress bar and summary.
 pub struct Display {
-    filters: Vec<Box<dyn ResponseFilter + Send + Sync>>,
     args: DisplayArgs,
+    template: Template,
+    progress: MultiProgress,
     progress_bar: ProgressBar,
+    /// Progress of each target, shown under the overall progress bar when there are several.
+    target_bars: HashMap<String, ProgressBar>,
+    /// Whether stdout is a terminal. When it is not, results are written bare to stdout
+    /// and everything else goes to stderr so that pipelines only see results.
+    terminal: bool,
+    /// Number of payloads of one scan, added to the progress bar for each recursive scan.
+    wordlist_size: usize,
+    /// Results kept for the ordered re-rendering at scan end, when `--sort` or `--group-by` is set.
+    pending: Vec<Arc<FuzzResponse>>,
 }
 
 impl Display {
-    pub fn new(args: DisplayArgs, wl_lines_count: usize) -> Self {
-        Self::headers(&args);
+    pub fn new(args: &DisplayArgs, wl_lines_count: usize) -> Self {
+        let terminal = io::stdout().is_terminal();
 
-        let progress_bar = ProgressBar::new(wl_lines_count as u64);
+        if args.no_color || !terminal || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            colored::control::set_override(false);
+        }
+
+        let progress = match args.silent || !terminal {
+            true => MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+            false => MultiProgress::new(),
+        };
+
+        let progress_bar = progress.add(ProgressBar::new((wl_lines_count * args.targets.len()) as u64));
         progress_bar.set_style(
             ProgressStyle::default_bar()
                 .template(":: Progress: [{pos}/{len}][{percent}%] :: Duration: {elapsed_precise} :: {per_sec}")
                 .unwrap()
         );
 
-        let mut display = Self {
-            filters: Vec::new(),
-            args,
+        let mut target_bars = HashMap::new();
+        if args.targets.len() > 1 {
+            for target in &args.targets {
+                let bar = progress.add(ProgressBar::new(wl_lines_count as u64));
+                bar.set_style(
+                    ProgressStyle::default_bar()
+                        .template("   [{pos}/{len}][{percent:>3}%] {prefix}")
+                        .unwrap()
+                );
+                bar.set_prefix(target.clone());
+                target_bars.insert(target.clone(), bar);
+            }
+        }
+
+        let template = args.template.clone().unwrap_or_default();
+
+        Self {
+            args: args.clone(),
+            template,
+            progress,
             progress_bar,
-        };
+            target_bars,
+            terminal,
+            wordlist_size: wl_lines_count,
+            pending: Vec::new(),
+        }
+    }
 
-        display.filters.push(Box::new(StatusCodeFilter::new(
-            display.args.filtered_code.clone(),
-            display.args.matched_code.clone(),
-        )));
+    fn headers(&self, args: &DisplayArgs) {
+        self.info("*=================================================*");
+        self.info("");
 
-        display.filters.push(Box::new(ContentSizeFilter::new(
-            display.args.filtered_size.clone(),
-            display.args.matched_size.clone(),
-        )));
+        for target in &args.targets {
+            self.info(format!("* {:<14} : {}", "URL".dimmed(), target));
+        }
+        self.info(format!("* {:<14} : {}", "Wordlist".dimmed(), args.wordlist));
+        self.info(format!("* {:<14} : {}", "Threads".dimmed(), args.threads));
+        if args.rate > 0 {
+            self.info(format!("* {:<14} : {} req/s", "Rate".dimmed(), args.rate));
+        }
+        self.info(format!("* {:<14} : {}", "Timeout".dimmed(), args.timeout));
+        self.info(format!("* {:<14} : {}", "User-Agent".dimmed(), args.user_agent));
 
-        display.filters.push(Box::new(WordFilter::new(
-            display.args.filtered_word.clone(),
-            display.args.matched_word.clone(),
-        )));
+        self.filters(args);
 
-        display
-    }
+        for calibration in &args.calibrations {
+            let describe = match args.targets.len() {
+                1 => calibration.describe(),
+                _ => format!("{} ({})", calibration.describe(), calibration.target),
+            };
+            self.info(format!("* {:<14} : {}", "Calibration".dimmed(), describe));
+        }
+        if let Some(similarity) = args.similarity {
+            self.info(format!("* {:<14} : {}%", "Similarity".dimmed(), similarity));
+        }
+        if args.dedupe {
+            self.info(format!("* {:<14} : enabled", "Dedupe".dimmed()));
+        }
+
+        if args.recursion_depth > 0 {
+            self.info(format!(
+                "* {:<14} : depth {}, {} strategy, on {:?}",
+                "Recursion".dimmed(),
+                args.recursion_depth,
+                args.recursion_strategy.as_str(),
+                range_formatted(&args.recursion_status)
+            ));
+        }
 
-    pub fn headers(args: &DisplayArgs) {
-        pub fn range_formatted<T>(range: &[T]) -> Vec<String>
-        where
-            T: Copy + PartialOrd + Add<Output = T> + fmt::Display + Ord + TryFrom<u64> + Into<u64>,
+        for seeds in &args.seeds {
+            let describe = match seeds.origin.is_empty() {
+                true => String::from("skipped, the target has no single origin"),
+                false => format!("{} paths from robots.txt and sitemaps", seeds.paths.len()),
+            };
+            let describe = match args.targets.len() {
+                1 => describe,
+                _ => format!("{} ({})", describe, seeds.target),
+            };
+            self.info(format!("* {:<14} : {}", "Seeds".dimmed(), describe));
+        }
+        if args.extract_links {
+            self.info(format!("* {:<14} : extracted from matched HTML responses", "Links".dimmed()));
+        }
+
+        if args.order.is_active() {
+            self.info(format!("* {:<14} : {}", "Order".dimmed(), args.order.describe()));
+        }
+        if args.limits.is_active() {
+            self.info(format!("* {:<14} : {}", "Limits".dimmed(), args.limits.describe()));
+        }
+
+        if let Some(resume) = &args.resume {
+            self.info(format!("* {:<14} : {}", "Resumed from".dimmed(), resume));
+        }
+        if let Some(checkpoint) = &args.checkpoint {
+            self.info(format!("* {:<14} : {}", "Checkpoint".dimmed(), checkpoint));
+        }
+
+        if let Some(output_dir) = &args.output_dir {
+            self.info(format!("* {:<14} : {}", "Output dir".dimmed(), output_dir));
+        }
+        for output in &args.outputs {
+            self.info(format!("* {:<14} : {} ({})", "Output".dimmed(), output.path, output.format.as_str()));
+        }
+        if args
+            .outputs
+            .iter()
+            .any(|output| matches!(output.format, OutputFormat::Csv | OutputFormat::Md))
         {
-            if range.is_empty() {
-                return vec![];
-            }
-        
-            let mut sorted = range.to_owned();
-            sorted.sort();
-        
-            let mut result = Vec::new();
-            let mut start = sorted[0];
-            let mut prev = sorted[0];
-            let mut count = 1;
-        
-            for &num in sorted.iter().skip(1) {
-                if num.into() == prev.into() + 1 {
-                    count += 1;
-                } else {
-                    if count >= 6 {
-                        result.push(format!("{}-{}", start, prev));
-                    } else {
-                        for n in start.into()..=prev.into() {
-                            result.push(n.to_string());
-                        }
-                    }
-                    start = num;
-                    count = 1;
-                }
-                prev = num;
-            }
-        
-            if count >= 6 {
-                result.push(format!("{}-{}", start, prev));
-            } else {
-                for n in start.into()..=prev.into() {
-                    result.push(n.to_string());
-                }
-            }
-        
-            result
+            let columns: Vec<&str> = args.columns.iter().map(|c| c.as_str()).collect();
+            self.info(format!("* {:<14} : {}", "Columns".dimmed(), columns.join(", ")));
         }
 
-        println!("*=================================================*");
-        println!();
+        self.info(format!("* {:<14} : {}", "Method".dimmed(), args.method));
+        if args.interactive {
+            self.info(format!("* {:<14} : press Enter to pause the scan", "Interactive".dimmed()));
+        }
+        self.info("");
 
-        println!("* {:<14} : {}", "URL".dimmed(), args.url);
-        println!("* {:<14} : {}", "Wordlist".dimmed(), args.wordlist);
-        println!("* {:<14} : {}", "Threads".dimmed(), args.threads);
-        println!("* {:<14} : {}", "Timeout".dimmed(), args.timeout);
-        println!("* {:<14} : {}", "User-Agent".dimmed(), args.user_agent);
+        self.info("*=================================================*");
+        self.info("");
+        if args.template.is_none() {
+            self.info(format!("{:<6} {:<6} {:<8} URL", "STATUS", "SIZE", "TIME"));
+        }
+    }
 
+    /// Lines of the matchers and filters in use.
+    fn filters(&self, args: &DisplayArgs) {
         if !args.filtered_code.is_empty() {
-            println!("* {:<14} : {:?}", "Filtered code".dimmed(), range_formatted(&args.filtered_code));
+            self.info(format!("* {:<14} : {:?}", "Filtered code".dimmed(), range_formatted(&args.filtered_code)));
         }
         if !args.filtered_size.is_empty() {
-            println!("* {:<14} : {:?}", "Filtered size".dimmed(), range_formatted(&args.filtered_size));
+            self.info(format!("* {:<14} : {:?}", "Filtered size".dimmed(), range_formatted(&args.filtered_size)));
+        }
+        if !args.filtered_word.is_empty() {
+            self.info(format!("* {:<14} : {:?}", "Filtered word".dimmed(), args.filtered_word));
         }
         if !args.matched_code.is_empty() {
-            println!("* {:<14} : {:?}", "Matched code".dimmed(), range_formatted(&args.matched_code));
+            self.info(format!("* {:<14} : {:?}", "Matched code".dimmed(), range_formatted(&args.matched_code)));
         }
         if !args.matched_size.is_empty() {
-            println!("* {:<14} : {:?}", "Matched size".dimmed(), range_formatted(&args.matched_size));
+            self.info(format!("* {:<14} : {:?}", "Matched size".dimmed(), range_formatted(&args.matched_size)));
+        }
+        if !args.matched_word.is_empty() {
+            self.info(format!("* {:<14} : {:?}", "Matched word".dimmed(), args.matched_word));
         }
 
-        println!("* {:<14} : {}", "Method".dimmed(), args.method);
-        println!();
+        if let Some(expression) = &args.expression {
+            self.info(format!("* {:<14} : {}", "Expression".dimmed(), expression.as_str()));
+        }
 
-        println!("*=================================================*");
-        println!();
-        println!("{:<6} {:<6} {:<8} URL", "STATUS", "SIZE", "TIME");
+        if !args.matched_code.is_empty() || !args.matched_size.is_empty() || !args.matched_word.is_empty() {
+            self.info(format!("* {:<14} : {}", "Match mode".dimmed(), args.match_mode.as_str()));
+        }
+        if !args.filtered_code.is_empty() || !args.filtered_size.is_empty() || !args.filtered_word.is_empty() {
+            self.info(format!("* {:<14} : {}", "Filter mode".dimmed(), args.filter_mode.as_str()));
+        }
     }
 
     fn status_formatter(status: u16) -> ColoredString {
@@ -136,42 +262,276 @@ impl Display {
         }
     }
 
-    fn size_formatter(size: u64) -> ColoredString {
-        match size {
-            0 => format!("{}", size).red().dimmed(),
-            _ => format!("{}", size).yellow().dimmed(),
+    /// Extra fields of verbose result lines: the redirect target of 3xx responses,
+    /// the content type and title of 2xx responses, and the payload when the URL does not show it.
+    fn details_formatter(response: &FuzzResponse) -> String {
+        let mut details = String::new();
+
+        match response.status {
+            300..=399 => {
+                if let Some(location) = response.redirect() {
+                    details.push_str(&format!(" {} {}", "->".blue(), location.blue()));
+                }
+            }
+            200..=299 => {
+                if let Some(content_type) = response.content_type() {
+                    details.push_str(&format!(" {}", format!("[{}]", content_type).dimmed()));
+                }
+                if let Some(title) = response.title() {
+                    details.push_str(&format!(" \"{}\"", title.cyan()));
+                }
+            }
+            _ => {}
+        }
+
+        if !response.payload.is_empty() && !response.url.contains(&response.payload) {
+            details.push_str(&format!(" {}", format!("(payload: {})", response.payload).dimmed()));
         }
+
+        details
     }
 
-    fn time_formatter(time: Duration) -> ColoredString {
-        let mut time = time.as_millis().to_string();
-        time.push_str("ms");
-        time.dimmed()
+    fn render(&self, response: &FuzzResponse) -> String {
+        // Silent mode prints bare URLs unless the user asked for another layout.
+        let mut line = match self.args.silent && self.args.template.is_none() {
+            true => response.url.clone(),
+            false => self.template.render(response, &self.args.method, true),
+        };
+        if self.args.verbose {
+            line.push_str(&Self::details_formatter(response));
+        }
+        if response.discovered && !self.args.silent {
+            line.push_str(&format!(" {}", "[discovered]".magenta()));
+        }
+        line
     }
 
-    pub fn log_response(&self, response: Response, time: Duration, url: &str) {
-        let status_code = response.status().as_u16();
-        let content_size = response.content_length().unwrap_or(0);
-        let body = response.text().unwrap();
+    /// Whether results are printed as they arrive. With an ordering, piped and silent scans
+    /// only print the ordered results at the end so that the output holds each result once.
+    fn streams(&self) -> bool {
+        !self.args.order.is_active() || (self.terminal && !self.args.silent)
+    }
 
-        if self
-            .filters
-            .iter()
-            .any(|filter| filter.should_filter(status_code, content_size, &body))
-        {
+    /// Prints the results kept during the scan again, sorted and grouped.
+    fn ordered_results(&mut self) {
+        let order = self.args.order;
+        if !order.is_active() {
+            return;
+        }
+
+        if self.streams() {
+            self.info("");
+            self.info("*=================================================*");
+            self.info("");
+            self.info(format!("* {:<14} : {}", "Results".dimmed(), order.describe()));
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        for group in order.arrange(pending) {
+            if let Some(label) = &group.label {
+                self.info("");
+                self.info(format!("{} ({})", label.bold(), group.responses.len()));
+            }
+            for response in &group.responses {
+                self.result(self.render(response));
+            }
+        }
+    }
+
+    /// Prints a result line to stdout, above the progress bar.
+    fn result(&self, line: impl fmt::Display) {
+        self.progress.suspend(|| println!("{}", line));
+    }
+
+    /// Prints anything that is not a result: nothing in silent mode,
+    /// stderr when stdout is piped.
+    fn info(&self, line: impl fmt::Display) {
+        match (self.args.silent, self.terminal) {
+            (true, _) => {}
+            (false, true) => self.progress_bar.suspend(|| println!("{}", line)),
+            (false, false) => eprintln!("{}", line),
+        }
+    }
+
+    fn error(&self, message: &str) {
+        self.progress.suspend(|| eprintln!("{}", message.red()));
+    }
+
+    fn summary(&self, summary: &ScanSummary) {
+        fn counts<K: fmt::Display>(counts: impl Iterator<Item = (K, usize)>) -> String {
+            counts
+                .map(|(key, count)| format!("{}: {}", key, count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        self.info("");
+        self.info("*=================================================*");
+        self.info("");
+        self.info(format!(
+            "* {:<14} : {} ({:.1} req/s)",
+            "Requests".dimmed(),
+            summary.total_requests,
+            summary.requests_per_second
+        ));
+        self.info(format!("* {:<14} : {}", "Matched".dimmed(), summary.matched));
+        self.info(format!("* {:<14} : {}", "Filtered".dimmed(), summary.filtered));
+        if self.args.dedupe {
+            self.info(format!("* {:<14} : {}", "Duplicates".dimmed(), summary.duplicates));
+        }
+
+        match summary.errored {
+            0 => self.info(format!("* {:<14} : 0", "Errors".dimmed())),
+            errored => self.info(format!(
+                "* {:<14} : {} ({})",
+                "Errors".dimmed(),
+                errored,
+                counts(summary.errors.iter().map(|(kind, count)| (kind.as_str(), *count)))
+            )),
+        }
+
+        if !summary.status_codes.is_empty() {
+            self.info(format!(
+                "* {:<14} : {}",
+                "Status codes".dimmed(),
+                counts(summary.status_codes.iter().map(|(status, count)| (status, *count)))
+            ));
+        }
+
+        self.info(format!(
+            "* {:<14} : avg {}ms, p95 {}ms",
+            "Latency".dimmed(),
+            summary.average_latency.as_millis(),
+            summary.p95_latency.as_millis()
+        ));
+
+        let elapsed = summary.elapsed.as_secs();
+        self.info(format!(
+            "* {:<14} : {:02}:{:02}:{:02}",
+            "Duration".dimmed(),
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60
+        ));
+
+        for template in &summary.expired_jobs {
+            self.info(format!("* {:<14} : {}", "Job timed out".dimmed(), template));
+        }
+
+        if let Some(reason) = summary.stopped {
+            self.info(format!("* {:<14} : {}", "Stopped".dimmed(), reason.as_str().yellow()));
+            if let Some(path) = &summary.checkpoint {
+                self.info(format!("* {:<14} : --resume {}", "Resume with".dimmed(), path));
+            }
+        }
+    }
+
+    fn duplicates(&self, summary: &ScanSummary) {
+        let duplicates = &summary.duplicate_groups;
+        if duplicates.is_empty() {
             return;
         }
 
-        self.progress_bar.println(format!(
-            "{:<6} {:<6} {:<8} {}",
-            Self::status_formatter(status_code),
-            Self::size_formatter(content_size),
-            Self::time_formatter(time),
-            url.trim()
+        let collapsed: usize = duplicates.iter().map(|group| group.count).sum();
+
+        self.info("");
+        self.info(format!(
+            "* {:<14} : {} responses collapsed into {} groups",
+            "Duplicates".dimmed(),
+            collapsed,
+            duplicates.len()
+        ));
+
+        for group in duplicates {
+            self.info(format!(
+                "  {} {} more like {}",
+                Self::status_formatter(group.status),
+                group.count,
+                group.first_url
+            ));
+            for example in &group.examples {
+                self.info(format!("      {}", example.dimmed()));
+            }
+        }
+    }
+}
+
+impl OutputSink for Display {
+    fn on_start(&mut self, args: &DisplayArgs) {
+        self.headers(args);
+    }
+
+    fn on_result(&mut self, response: &Arc<FuzzResponse>) {
+        if self.streams() {
+            self.result(self.render(response));
+        }
+        if self.args.order.is_active() {
+            self.pending.push(Arc::clone(response));
+        }
+    }
+
+    fn on_job(&mut self, job: &Job) {
+        self.progress_bar.inc_length(self.wordlist_size as u64);
+        if let Some(bar) = self.target_bars.get(&job.target) {
+            bar.inc_length(self.wordlist_size as u64);
+        }
+        self.info(format!(
+            "{} {} (depth {})",
+            "Queued recursive scan of".dimmed(),
+            job.template,
+            job.depth
         ));
     }
 
-    pub fn increment_progress_bar(&self) {
-        self.progress_bar.inc(1);
+    fn on_links(&mut self, job: &Job, paths: &[String]) {
+        self.progress_bar.inc_length(paths.len() as u64);
+        if let Some(bar) = self.target_bars.get(&job.target) {
+            bar.inc_length(paths.len() as u64);
+        }
+        if self.args.verbose {
+            self.info(format!("{} {} links", "Queued discovered".dimmed(), paths.len()));
+        }
+    }
+
+    fn on_error(&mut self, url: &str, kind: ErrorKind) {
+        if self.args.verbose {
+            self.error(&format!("{} ({})", url, kind.as_str()));
+        }
+    }
+
+    fn on_progress(&mut self, job: &Job, count: usize) {
+        self.progress_bar.inc(count as u64);
+        if let Some(bar) = self.target_bars.get(&job.target) {
+            bar.inc(count as u64);
+        }
+    }
+
+    fn on_pause(&mut self) {
+        self.progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    fn on_resume(&mut self) {
+        if !self.args.silent && self.terminal {
+            self.progress.set_draw_target(ProgressDrawTarget::stderr());
+        }
+    }
+
+    fn on_update(&mut self, args: &DisplayArgs) {
+        self.args = args.clone();
+        self.info(format!("{}", "Filters updated".yellow()));
+        self.filters(args);
+    }
+
+    fn on_finish(&mut self, summary: &ScanSummary) {
+        // A stopped scan leaves its bars where it stopped.
+        for bar in self.target_bars.values().chain([&self.progress_bar]) {
+            match summary.stopped {
+                Some(_) => bar.abandon(),
+                None => bar.finish(),
+            }
+        }
+        self.ordered_results();
+        self.summary(summary);
+        self.duplicates(summary);
     }
 }
@@ -0,0 +1,195 @@
+use crate::expression::ExpressionError;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Token {
+    Ident(String),
+    Number(u64),
+    Str(String),
+    Regex(String, String),
+    And,
+    Or,
+    Not,
+    In,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Tilde,
+    NotTilde,
+    Range,
+    RangeInclusive,
+}
+
+/// A token and the 1-based column where it starts.
+pub struct Spanned {
+    pub token: Token,
+    pub column: usize,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Eq, 2),
+            ('!', Some('=')) => (Token::Ne, 2),
+            ('!', Some('~')) => (Token::NotTilde, 2),
+            ('<', Some('=')) => (Token::Le, 2),
+            ('>', Some('=')) => (Token::Ge, 2),
+            ('.', Some('.')) if chars.get(i + 2) == Some(&'=') => (Token::RangeInclusive, 3),
+            ('.', Some('.')) => (Token::Range, 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', _) => (Token::Lt, 1),
+            ('>', _) => (Token::Gt, 1),
+            ('~', _) => (Token::Tilde, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            (',', _) => (Token::Comma, 1),
+            ('"', _) => {
+                let (value, end) = read_delimited(&chars, i, '"')
+                    .ok_or(ExpressionError::UnterminatedString(column))?;
+                (Token::Str(value), end - i)
+            }
+            ('/', _) => {
+                let (pattern, mut end) = read_delimited(&chars, i, '/')
+                    .ok_or(ExpressionError::UnterminatedRegex(column))?;
+                let mut flags = String::new();
+                while end < chars.len() && chars[end].is_ascii_alphabetic() {
+                    flags.push(chars[end]);
+                    end += 1;
+                }
+                (Token::Regex(pattern, flags), end - i)
+            }
+            (c, _) if c.is_ascii_digit() => {
+                let end = scan(&chars, i, |c| c.is_ascii_digit());
+                let number: String = chars[i..end].iter().collect();
+                let number = number
+                    .parse()
+                    .map_err(|_| ExpressionError::InvalidNumber(column))?;
+                (Token::Number(number), end - i)
+            }
+            (c, _) if c.is_ascii_alphabetic() => {
+                let end = scan(&chars, i, |c| c.is_ascii_alphanumeric() || "_-.".contains(c));
+                let ident: String = chars[i..end].iter().collect();
+                let token = match ident.as_str() {
+                    "in" => Token::In,
+                    _ => Token::Ident(ident),
+                };
+                (token, end - i)
+            }
+            _ => return Err(ExpressionError::UnexpectedCharacter(column)),
+        };
+
+        tokens.push(Spanned { token, column });
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+fn scan(chars: &[char], start: usize, accept: impl Fn(char) -> bool) -> usize {
+    let mut end = start;
+    while end < chars.len() && accept(chars[end]) {
+        end += 1;
+    }
+    end
+}
+
+/// Reads a literal opened by `delimiter` at `start`, handling backslash escapes
+/// of the delimiter. Returns the unescaped content and the index past the closing delimiter.
+fn read_delimited(chars: &[char], start: usize, delimiter: char) -> Option<(String, usize)> {
+    let mut value = String::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&delimiter) => {
+                value.push(delimiter);
+                i += 2;
+            }
+            c if c == delimiter => return Some((value, i + 1)),
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<(Token, usize)> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| (spanned.token, spanned.column))
+            .collect()
+    }
+
+    #[test]
+    fn prefers_the_longest_operator() {
+        assert_eq!(
+            tokens("a<=1..=2!~b"),
+            vec![
+                (Token::Ident(String::from("a")), 1),
+                (Token::Le, 2),
+                (Token::Number(1), 4),
+                (Token::RangeInclusive, 5),
+                (Token::Number(2), 8),
+                (Token::NotTilde, 9),
+                (Token::Ident(String::from("b")), 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_regex_flags_and_dotted_names() {
+        assert_eq!(
+            tokens("header.content-type ~ /json/im"),
+            vec![
+                (Token::Ident(String::from("header.content-type")), 1),
+                (Token::Tilde, 21),
+                (Token::Regex(String::from("json"), String::from("im")), 23),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        assert_eq!(
+            tokens(r#""é" in"#),
+            vec![(Token::Str(String::from("é")), 1), (Token::In, 5)]
+        );
+    }
+
+    #[test]
+    fn keeps_other_backslashes() {
+        assert_eq!(tokens(r"/\d+/"), vec![(Token::Regex(String::from(r"\d+"), String::new()), 1)]);
+    }
+}
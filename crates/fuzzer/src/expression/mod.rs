@@ -0,0 +1,347 @@
+pub mod lexer;
+pub mod parser;
+
+use crate::response::FuzzResponse;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpressionError {
+    UnexpectedCharacter(usize),
+    UnterminatedString(usize),
+    UnterminatedRegex(usize),
+    InvalidRegex(usize),
+    InvalidNumber(usize),
+    UnknownField(usize),
+    ExpectedField(usize),
+    ExpectedOperator(usize),
+    ExpectedValue(usize),
+    ExpectedClosingParen(usize),
+    ExpectedClosingBracket(usize),
+    TypeMismatch(usize),
+    UnexpectedToken(usize),
+    UnexpectedEnd(usize),
+}
+
+impl ExpressionError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpressionError::UnexpectedCharacter(_) => "Unexpected character",
+            ExpressionError::UnterminatedString(_) => "Unterminated string literal",
+            ExpressionError::UnterminatedRegex(_) => "Unterminated regular expression",
+            ExpressionError::InvalidRegex(_) => "Invalid regular expression",
+            ExpressionError::InvalidNumber(_) => "Invalid number",
+            ExpressionError::UnknownField(_) => "Unknown field",
+            ExpressionError::ExpectedField(_) => "Expected a field name",
+            ExpressionError::ExpectedOperator(_) => "Expected a comparison operator",
+            ExpressionError::ExpectedValue(_) => "Expected a value",
+            ExpressionError::ExpectedClosingParen(_) => "Expected `)`",
+            ExpressionError::ExpectedClosingBracket(_) => "Expected `]`",
+            ExpressionError::TypeMismatch(_) => "Value type does not fit the field or operator",
+            ExpressionError::UnexpectedToken(_) => "Unexpected token",
+            ExpressionError::UnexpectedEnd(_) => "Unexpected end of expression",
+        }
+    }
+
+    /// 1-based column in the source where the error was detected.
+    pub fn column(&self) -> usize {
+        match *self {
+            ExpressionError::UnexpectedCharacter(c)
+            | ExpressionError::UnterminatedString(c)
+            | ExpressionError::UnterminatedRegex(c)
+            | ExpressionError::InvalidRegex(c)
+            | ExpressionError::InvalidNumber(c)
+            | ExpressionError::UnknownField(c)
+            | ExpressionError::ExpectedField(c)
+            | ExpressionError::ExpectedOperator(c)
+            | ExpressionError::ExpectedValue(c)
+            | ExpressionError::ExpectedClosingParen(c)
+            | ExpressionError::ExpectedClosingBracket(c)
+            | ExpressionError::TypeMismatch(c)
+            | ExpressionError::UnexpectedToken(c)
+            | ExpressionError::UnexpectedEnd(c) => c,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Field {
+    Status,
+    Size,
+    Words,
+    Lines,
+    /// Response time in milliseconds.
+    Time,
+    Body,
+    Url,
+    Payload,
+    Header(String),
+}
+
+impl Field {
+    pub fn from_name(name: &str) -> Option<Field> {
+        let field = match name {
+            "status" => Field::Status,
+            "size" => Field::Size,
+            "words" => Field::Words,
+            "lines" => Field::Lines,
+            "time" => Field::Time,
+            "body" => Field::Body,
+            "url" => Field::Url,
+            "payload" => Field::Payload,
+            _ => match name.strip_prefix("header.") {
+                Some(header) if !header.is_empty() => Field::Header(header.to_string()),
+                _ => return None,
+            },
+        };
+
+        Some(field)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Field::Status | Field::Size | Field::Words | Field::Lines | Field::Time
+        )
+    }
+
+    fn number(&self, response: &FuzzResponse) -> u64 {
+        match self {
+            Field::Status => response.status as u64,
+            Field::Size => response.size,
+            Field::Words => response.words as u64,
+            Field::Lines => response.lines as u64,
+            Field::Time => response.time.as_millis() as u64,
+            _ => 0,
+        }
+    }
+
+    fn text<'a>(&self, response: &'a FuzzResponse) -> &'a str {
+        match self {
+            Field::Body => &response.body,
+            Field::Url => &response.url,
+            Field::Payload => &response.payload,
+            Field::Header(name) => response.header(name).unwrap_or(""),
+            _ => "",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ordering {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Ordering {
+    fn compare(&self, left: u64, right: u64) -> bool {
+        match self {
+            Ordering::Eq => left == right,
+            Ordering::Ne => left != right,
+            Ordering::Lt => left < right,
+            Ordering::Le => left <= right,
+            Ordering::Gt => left > right,
+            Ordering::Ge => left >= right,
+        }
+    }
+}
+
+/// Inclusive bounds of a set member; single values have equal bounds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Bounds(pub u64, pub u64);
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Field, Ordering, u64),
+    In(Field, Vec<Bounds>),
+    Equals(Field, String),
+    Contains(Field, String),
+    Matches(Field, Regex),
+}
+
+impl Node {
+    fn evaluate(&self, response: &FuzzResponse) -> bool {
+        match self {
+            Node::And(left, right) => left.evaluate(response) && right.evaluate(response),
+            Node::Or(left, right) => left.evaluate(response) || right.evaluate(response),
+            Node::Not(node) => !node.evaluate(response),
+            Node::Compare(field, ordering, value) => {
+                ordering.compare(field.number(response), *value)
+            }
+            Node::In(field, set) => {
+                let value = field.number(response);
+                set.iter().any(|Bounds(start, end)| (*start..=*end).contains(&value))
+            }
+            Node::Equals(field, value) => field.text(response) == value,
+            Node::Contains(field, value) => field.text(response).contains(value.as_str()),
+            Node::Matches(field, regex) => regex.is_match(field.text(response)),
+        }
+    }
+}
+
+/// A response predicate written in rake's expression language, e.g.
+/// `status in 200..300 && size > 1000 && !body ~ /not found/i && time < 500`.
+#[derive(Clone, Debug)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let tokens = lexer::tokenize(source)?;
+        let root = parser::Parser::new(tokens, source.chars().count()).parse()?;
+
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    pub fn evaluate(&self, response: &FuzzResponse) -> bool {
+        self.root.evaluate(response)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> FuzzResponse {
+        let mut response = FuzzResponse::fixture(200, "http://target/admin", "admin", "<h1>Admin panel</h1>\nWelcome back");
+        response.headers.insert("server", "nginx/1.25".parse().unwrap());
+        response
+    }
+
+    fn evaluate(source: &str) -> bool {
+        Expression::parse(source).unwrap_or_else(|e| panic!("{}: {}", source, e.as_str())).evaluate(&response())
+    }
+
+    fn error(source: &str) -> ExpressionError {
+        Expression::parse(source).err().unwrap_or_else(|| panic!("{} parsed", source))
+    }
+
+    #[test]
+    fn compares_numeric_fields() {
+        assert!(evaluate("status == 200"));
+        assert!(evaluate("status != 404"));
+        assert!(evaluate("size > 10 && size <= 100"));
+        assert!(evaluate("words == 4"));
+        assert!(evaluate("lines < 3"));
+        assert!(evaluate("time < 500"));
+        assert!(!evaluate("status > 200"));
+    }
+
+    #[test]
+    fn checks_set_membership() {
+        assert!(evaluate("status in 200..300"));
+        assert!(!evaluate("status in 100..200"));
+        assert!(evaluate("status in 100..=200"));
+        assert!(evaluate("status in [301, 200..=204]"));
+        assert!(evaluate("status in 200"));
+        assert!(!evaluate("status in [301, 403]"));
+    }
+
+    #[test]
+    fn matches_text_fields() {
+        assert!(evaluate(r#"body ~ "Admin panel""#));
+        assert!(evaluate("body ~ /admin PANEL/i"));
+        assert!(!evaluate("body ~ /admin PANEL/"));
+        assert!(evaluate("body !~ /not found/"));
+        assert!(evaluate(r#"payload == "admin""#));
+        assert!(evaluate(r#"url != "http://target/""#));
+        assert!(evaluate(r#"header.server ~ "nginx""#));
+        assert!(evaluate(r#"header.x-missing == """#));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // (false && true) || true
+        assert!(evaluate("status == 404 && size > 0 || words > 0"));
+        // false && (true || true) would be false.
+        assert!(evaluate("words > 0 || status == 404 && size > 0"));
+        assert!(!evaluate("(words > 0 || status == 404) && size == 0"));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert!(!evaluate("!status == 200 && size > 0"));
+        assert!(evaluate("!(status == 200 && size == 0)"));
+        assert!(evaluate("!!status == 200"));
+    }
+
+    #[test]
+    fn unescapes_delimiters_in_literals() {
+        let mut response = response();
+        response.body = String::from(r#"say "hi" to a/b"#);
+
+        assert!(Expression::parse(r#"body ~ "\"hi\"""#).unwrap().evaluate(&response));
+        assert!(Expression::parse(r"body ~ /a\/b/").unwrap().evaluate(&response));
+    }
+
+    #[test]
+    fn keeps_its_source() {
+        let source = "status in 200..300 && !body ~ /not found/i";
+        assert_eq!(Expression::parse(source).unwrap().as_str(), source);
+    }
+
+    #[test]
+    fn reports_unterminated_literals() {
+        assert_eq!(error(r#"body ~ "admin"#), ExpressionError::UnterminatedString(8));
+        assert_eq!(error("body ~ /admin"), ExpressionError::UnterminatedRegex(8));
+    }
+
+    #[test]
+    fn reports_lexical_errors() {
+        assert_eq!(error("status = 200"), ExpressionError::UnexpectedCharacter(8));
+        assert_eq!(error("status == 99999999999999999999"), ExpressionError::InvalidNumber(11));
+        assert_eq!(error("body ~ /a/q"), ExpressionError::InvalidRegex(8));
+        assert_eq!(error("body ~ /(/"), ExpressionError::InvalidRegex(8));
+    }
+
+    #[test]
+    fn reports_unknown_fields() {
+        assert_eq!(error("code == 200"), ExpressionError::UnknownField(1));
+        assert_eq!(error("header. == \"x\""), ExpressionError::UnknownField(1));
+        assert_eq!(error("200 == status"), ExpressionError::ExpectedField(1));
+    }
+
+    #[test]
+    fn reports_type_mismatches() {
+        assert_eq!(error(r#"status == "200""#), ExpressionError::TypeMismatch(11));
+        assert_eq!(error("body == 200"), ExpressionError::TypeMismatch(9));
+        assert_eq!(error("status ~ /2../"), ExpressionError::TypeMismatch(8));
+        assert_eq!(error("body in 1..2"), ExpressionError::TypeMismatch(6));
+        assert_eq!(error(r#"body < "a""#), ExpressionError::TypeMismatch(8));
+    }
+
+    #[test]
+    fn reports_incomplete_expressions() {
+        assert_eq!(error(""), ExpressionError::UnexpectedEnd(1));
+        assert_eq!(error("status"), ExpressionError::UnexpectedEnd(7));
+        assert_eq!(error("status =="), ExpressionError::UnexpectedEnd(10));
+        assert_eq!(error("status == 200 &&"), ExpressionError::UnexpectedEnd(17));
+        assert_eq!(error("(status == 200"), ExpressionError::ExpectedClosingParen(15));
+        assert_eq!(error("status in [200, 301"), ExpressionError::ExpectedClosingBracket(20));
+        assert_eq!(error("status == 200 )"), ExpressionError::UnexpectedToken(15));
+        assert_eq!(error("status in 300..200"), ExpressionError::InvalidNumber(16));
+        assert_eq!(error("status in 200..200"), ExpressionError::InvalidNumber(16));
+    }
+}
@@ -0,0 +1,319 @@
+use crate::expression::{
+    Bounds, ExpressionError, Field, Node, Ordering,
+    lexer::{Spanned, Token},
+};
+use regex::RegexBuilder;
+
+/// Recursive descent parser. Precedence, from loosest to tightest:
+/// `||`, `&&`, `!`, then a single comparison or a parenthesised expression.
+pub struct Parser {
+    tokens: Vec<Spanned>,
+    position: usize,
+    end_column: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Spanned>, source_len: usize) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            end_column: source_len + 1,
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Node, ExpressionError> {
+        let node = self.parse_or()?;
+
+        match self.tokens.get(self.position) {
+            None => Ok(node),
+            Some(spanned) => Err(ExpressionError::UnexpectedToken(spanned.column)),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|spanned| &spanned.token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|spanned| spanned.column)
+            .unwrap_or(self.end_column)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|spanned| spanned.token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.parse_unary()?));
+        }
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.eat(&Token::Not) {
+            return Ok(Node::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ExpressionError> {
+        let column = self.column();
+
+        match self.advance() {
+            Some(Token::LParen) => {
+                let node = self.parse_or()?;
+                match self.eat(&Token::RParen) {
+                    true => Ok(node),
+                    false => Err(ExpressionError::ExpectedClosingParen(self.column())),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                let field =
+                    Field::from_name(&name).ok_or(ExpressionError::UnknownField(column))?;
+                self.parse_comparison(field)
+            }
+            Some(_) => Err(ExpressionError::ExpectedField(column)),
+            None => Err(ExpressionError::UnexpectedEnd(column)),
+        }
+    }
+
+    fn parse_comparison(&mut self, field: Field) -> Result<Node, ExpressionError> {
+        let column = self.column();
+
+        let ordering = match self.advance() {
+            Some(Token::Eq) => Ordering::Eq,
+            Some(Token::Ne) => Ordering::Ne,
+            Some(Token::Lt) => Ordering::Lt,
+            Some(Token::Le) => Ordering::Le,
+            Some(Token::Gt) => Ordering::Gt,
+            Some(Token::Ge) => Ordering::Ge,
+            Some(Token::In) if field.is_numeric() => {
+                return Ok(Node::In(field, self.parse_set()?));
+            }
+            Some(Token::Tilde) if !field.is_numeric() => return self.parse_match(field),
+            Some(Token::NotTilde) if !field.is_numeric() => {
+                return Ok(Node::Not(Box::new(self.parse_match(field)?)));
+            }
+            Some(Token::In | Token::Tilde | Token::NotTilde) => {
+                return Err(ExpressionError::TypeMismatch(column));
+            }
+            Some(_) => return Err(ExpressionError::ExpectedOperator(column)),
+            None => return Err(ExpressionError::UnexpectedEnd(column)),
+        };
+
+        let column = self.column();
+
+        match (self.advance(), field.is_numeric()) {
+            (Some(Token::Number(value)), true) => Ok(Node::Compare(field, ordering, value)),
+            (Some(Token::Str(value)), false) if ordering == Ordering::Eq => {
+                Ok(Node::Equals(field, value))
+            }
+            (Some(Token::Str(value)), false) if ordering == Ordering::Ne => {
+                Ok(Node::Not(Box::new(Node::Equals(field, value))))
+            }
+            (Some(Token::Number(_) | Token::Str(_) | Token::Regex(..)), _) => {
+                Err(ExpressionError::TypeMismatch(column))
+            }
+            (Some(_), _) => Err(ExpressionError::ExpectedValue(column)),
+            (None, _) => Err(ExpressionError::UnexpectedEnd(column)),
+        }
+    }
+
+    fn parse_match(&mut self, field: Field) -> Result<Node, ExpressionError> {
+        let column = self.column();
+
+        match self.advance() {
+            Some(Token::Str(value)) => Ok(Node::Contains(field, value)),
+            Some(Token::Regex(pattern, flags)) => {
+                let mut builder = RegexBuilder::new(&pattern);
+                for flag in flags.chars() {
+                    match flag {
+                        'i' => builder.case_insensitive(true),
+                        'm' => builder.multi_line(true),
+                        's' => builder.dot_matches_new_line(true),
+                        'x' => builder.ignore_whitespace(true),
+                        _ => return Err(ExpressionError::InvalidRegex(column)),
+                    };
+                }
+                let regex = builder
+                    .build()
+                    .map_err(|_| ExpressionError::InvalidRegex(column))?;
+                Ok(Node::Matches(field, regex))
+            }
+            Some(Token::Number(_)) => Err(ExpressionError::TypeMismatch(column)),
+            Some(_) => Err(ExpressionError::ExpectedValue(column)),
+            None => Err(ExpressionError::UnexpectedEnd(column)),
+        }
+    }
+
+    /// Parses `a..b`, `a..=b`, a single number, or a bracketed list of those.
+    fn parse_set(&mut self) -> Result<Vec<Bounds>, ExpressionError> {
+        if !self.eat(&Token::LBracket) {
+            return Ok(vec![self.parse_bounds()?]);
+        }
+
+        let mut set = vec![self.parse_bounds()?];
+        while self.eat(&Token::Comma) {
+            set.push(self.parse_bounds()?);
+        }
+
+        match self.eat(&Token::RBracket) {
+            true => Ok(set),
+            false => Err(ExpressionError::ExpectedClosingBracket(self.column())),
+        }
+    }
+
+    fn parse_bounds(&mut self) -> Result<Bounds, ExpressionError> {
+        let start = self.parse_number()?;
+
+        if self.eat(&Token::RangeInclusive) {
+            let column = self.column();
+            let end = self.parse_number()?;
+            return match end >= start {
+                true => Ok(Bounds(start, end)),
+                false => Err(ExpressionError::InvalidNumber(column)),
+            };
+        }
+
+        if self.eat(&Token::Range) {
+            let column = self.column();
+            let end = self.parse_number()?;
+            return match end > start {
+                true => Ok(Bounds(start, end - 1)),
+                false => Err(ExpressionError::InvalidNumber(column)),
+            };
+        }
+
+        Ok(Bounds(start, start))
+    }
+
+    fn parse_number(&mut self) -> Result<u64, ExpressionError> {
+        let column = self.column();
+
+        match self.advance() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(_) => Err(ExpressionError::ExpectedValue(column)),
+            None => Err(ExpressionError::UnexpectedEnd(column)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::lexer::tokenize;
+
+    fn parse(source: &str) -> Result<Node, ExpressionError> {
+        Parser::new(tokenize(source)?, source.chars().count()).parse()
+    }
+
+    /// Renders a tree as an s-expression, regexes as `/pattern/`.
+    fn tree(source: &str) -> String {
+        fn render(node: &Node) -> String {
+            match node {
+                Node::And(left, right) => format!("(and {} {})", render(left), render(right)),
+                Node::Or(left, right) => format!("(or {} {})", render(left), render(right)),
+                Node::Not(node) => format!("(not {})", render(node)),
+                Node::Compare(field, ordering, value) => format!("({:?} {:?} {})", field, ordering, value),
+                Node::In(field, set) => format!("({:?} in {:?})", field, set),
+                Node::Equals(field, value) => format!("({:?} == {:?})", field, value),
+                Node::Contains(field, value) => format!("({:?} ~ {:?})", field, value),
+                Node::Matches(field, regex) => format!("({:?} ~ /{}/)", field, regex.as_str()),
+            }
+        }
+
+        render(&parse(source).unwrap_or_else(|e| panic!("{}: {}", source, e.as_str())))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            tree("status == 1 || size == 2 && words == 3"),
+            "(or (Status Eq 1) (and (Size Eq 2) (Words Eq 3)))"
+        );
+        assert_eq!(
+            tree("(status == 1 || size == 2) && words == 3"),
+            "(and (or (Status Eq 1) (Size Eq 2)) (Words Eq 3))"
+        );
+    }
+
+    #[test]
+    fn chains_associate_to_the_left() {
+        assert_eq!(
+            tree("status == 1 && size == 2 && words == 3"),
+            "(and (and (Status Eq 1) (Size Eq 2)) (Words Eq 3))"
+        );
+    }
+
+    #[test]
+    fn not_applies_to_the_next_operand() {
+        assert_eq!(tree("!status == 1 && size == 2"), "(and (not (Status Eq 1)) (Size Eq 2))");
+        assert_eq!(tree("!(status == 1 && size == 2)"), "(not (and (Status Eq 1) (Size Eq 2)))");
+        assert_eq!(tree("!!lines < 3"), "(not (not (Lines Lt 3)))");
+    }
+
+    #[test]
+    fn negated_operators_wrap_their_comparison() {
+        assert_eq!(tree(r#"url != "/""#), r#"(not (Url == "/"))"#);
+        assert_eq!(tree("body !~ /error/"), "(not (Body ~ /error/))");
+        assert_eq!(tree("status != 404"), "(Status Ne 404)");
+    }
+
+    #[test]
+    fn tilde_takes_a_string_or_a_regex() {
+        assert_eq!(tree(r#"body ~ "admin""#), r#"(Body ~ "admin")"#);
+        assert_eq!(tree(r"header.server ~ /nginx\/1\./"), r#"(Header("server") ~ /nginx/1\./)"#);
+
+        let Ok(Node::Matches(_, regex)) = parse("body ~ /^admin$/im") else {
+            panic!("not a regex");
+        };
+        assert!(regex.is_match("home\nADMIN\n"));
+    }
+
+    #[test]
+    fn reads_sets_as_inclusive_bounds() {
+        assert_eq!(
+            tree("status in [200, 300..303, 400..=404]"),
+            "(Status in [Bounds(200, 200), Bounds(300, 302), Bounds(400, 404)])"
+        );
+        assert_eq!(tree("size in 1..=1"), "(Size in [Bounds(1, 1)])");
+    }
+
+    #[test]
+    fn reports_the_column_of_the_offending_token() {
+        let error = |source: &str| parse(source).err().unwrap_or_else(|| panic!("{} parsed", source));
+
+        assert_eq!(error("status == 200 size"), ExpressionError::UnexpectedToken(15));
+        assert_eq!(error("status 200"), ExpressionError::ExpectedOperator(8));
+        assert_eq!(error("status == ("), ExpressionError::ExpectedValue(11));
+        assert_eq!(error("(status == 1 && )"), ExpressionError::ExpectedField(17));
+        assert_eq!(error("status in [1 2]"), ExpressionError::ExpectedClosingBracket(14));
+        assert_eq!(error("status in [1, ]"), ExpressionError::ExpectedValue(15));
+        assert_eq!(error("status in 5..=3"), ExpressionError::InvalidNumber(15));
+        assert_eq!(error("size !~ 3"), ExpressionError::TypeMismatch(6));
+        assert_eq!(error("body ~ 3"), ExpressionError::TypeMismatch(8));
+        assert_eq!(error("body ~ ("), ExpressionError::ExpectedValue(8));
+    }
+}
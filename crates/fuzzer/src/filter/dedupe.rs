@@ -0,0 +1,269 @@
+use crate::response::FuzzResponse;
+use crate::similarity::strip_reflections;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+};
+
+/// Number of duplicate URLs kept as examples for each group.
+const MAX_EXAMPLES: usize = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub status: u16,
+    pub first_url: String,
+    pub count: usize,
+    pub examples: Vec<String>,
+}
+
+/// First response of a group, which later responses are compared to.
+/// Originals are saved in checkpoints, for a resumed scan to collapse the same pages.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Original {
+    payload: String,
+    /// Number of tokens of the body.
+    tokens: usize,
+    /// Tokens of the body reflecting the payload, with their index.
+    reflections: Vec<(usize, String)>,
+    /// Hash of the other tokens.
+    hash: u64,
+    group: DuplicateGroup,
+}
+
+impl Original {
+    fn new(response: &FuzzResponse, tokens: &[&str]) -> Self {
+        let reflections: Vec<(usize, String)> = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| reflects(token, &response.payload))
+            .map(|(i, token)| (i, token.to_string()))
+            .collect();
+        let reflected: Vec<usize> = reflections.iter().map(|(i, _)| *i).collect();
+
+        Self {
+            payload: response.payload.clone(),
+            tokens: tokens.len(),
+            hash: hash_except(tokens, &reflected),
+            reflections,
+            group: DuplicateGroup {
+                status: response.status,
+                first_url: response.url.clone(),
+                count: 0,
+                examples: Vec::new(),
+            },
+        }
+    }
+
+    /// Indices of the tokens reflecting the original payload.
+    fn reflected(&self) -> Vec<usize> {
+        self.reflections.iter().map(|(i, _)| *i).collect()
+    }
+
+    /// Whether `tokens`, the body of a response to `payload` whose other tokens hash like the
+    /// original ones, also match at the reflections. Tokens that reflected the original payload
+    /// may be left as they were, as the original payload can appear in the page itself.
+    fn matches(&self, tokens: &[&str], payload: &str) -> bool {
+        self.reflections.iter().all(|(i, original)| {
+            tokens[*i] == original
+                || strip_reflections(tokens[*i], payload) == strip_reflections(original, &self.payload)
+        })
+    }
+}
+
+/// Hashes the tokens, except those at the sorted `indices`.
+fn hash_except(tokens: &[&str], indices: &[usize]) -> u64 {
+    let mut skipped = indices.iter().peekable();
+    let mut hasher = DefaultHasher::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if skipped.next_if_eq(&&i).is_none() {
+            token.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Originals whose payload was reflected at the same token indices, such as the pages
+/// of one template, keyed by the hash of their other tokens.
+#[derive(Default)]
+struct Shape {
+    reflected: Vec<usize>,
+    originals: HashMap<u64, Vec<Original>>,
+}
+
+/// Whether `token` holds `payload` as a whole token or path segment.
+fn reflects(token: &str, payload: &str) -> bool {
+    !payload.is_empty() && strip_reflections(token, payload) != token
+}
+
+/// Remembers the responses already shown, grouped by status and body, the reflections
+/// of the requested payload aside.
+pub struct Deduplicator {
+    /// Shapes keyed by status and number of body tokens, which reflections do not change.
+    /// A response is hashed once per shape, and looked up in its originals by that hash.
+    shapes: Mutex<HashMap<(u16, usize), Vec<Shape>>>,
+}
+
+impl Deduplicator {
+    pub fn new() -> Self {
+        Self {
+            shapes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records `response`, returning true when an identical one was already seen.
+    pub fn is_duplicate(&self, response: &FuzzResponse) -> bool {
+        let tokens: Vec<&str> = response.body.split_whitespace().collect();
+        let mut shapes = self.shapes.lock().unwrap();
+        let shapes = shapes.entry((response.status, tokens.len())).or_default();
+
+        let original = shapes.iter_mut().find_map(|shape| {
+            let hash = hash_except(&tokens, &shape.reflected);
+            shape
+                .originals
+                .get_mut(&hash)?
+                .iter_mut()
+                .find(|original| original.matches(&tokens, &response.payload))
+        });
+
+        match original {
+            Some(original) => {
+                let group = &mut original.group;
+                group.count += 1;
+                if group.examples.len() < MAX_EXAMPLES {
+                    group.examples.push(response.url.clone());
+                }
+                true
+            }
+            None => {
+                insert(shapes, Original::new(response, &tokens));
+                false
+            }
+        }
+    }
+
+    /// Every original seen so far, to save a checkpoint.
+    pub fn snapshot(&self) -> Vec<Original> {
+        let shapes = self.shapes.lock().unwrap();
+        originals(&shapes).cloned().collect()
+    }
+
+    /// Adds the originals of a resumed scan.
+    pub fn restore(&self, originals: Vec<Original>) {
+        let mut shapes = self.shapes.lock().unwrap();
+        for original in originals {
+            let shapes = shapes.entry((original.group.status, original.tokens)).or_default();
+            insert(shapes, original);
+        }
+    }
+
+    /// Groups that collapsed at least one response, largest first.
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        let shapes = self.shapes.lock().unwrap();
+        let mut groups: Vec<DuplicateGroup> = originals(&shapes)
+            .map(|original| original.group.clone())
+            .filter(|group| group.count > 0)
+            .collect();
+
+        groups.sort_by_key(|group| Reverse(group.count));
+        groups
+    }
+}
+
+fn originals(shapes: &HashMap<(u16, usize), Vec<Shape>>) -> impl Iterator<Item = &Original> {
+    shapes
+        .values()
+        .flatten()
+        .flat_map(|shape| shape.originals.values().flatten())
+}
+
+/// Files `original` under the shape of its reflections.
+fn insert(shapes: &mut Vec<Shape>, original: Original) {
+    let reflected = original.reflected();
+    let index = match shapes.iter().position(|shape| shape.reflected == reflected) {
+        Some(index) => index,
+        None => {
+            shapes.push(Shape { reflected, originals: HashMap::new() });
+            shapes.len() - 1
+        }
+    };
+    shapes[index].originals.entry(original.hash).or_default().push(original);
+}
+
+impl Default for Deduplicator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catch_all(payload: &str) -> FuzzResponse {
+        let body = format!("<nav><a href=\"/login\">login</a> <a href=\"/docs\">docs</a></nav>\n<p>/{} was not found</p>", payload);
+        FuzzResponse::fixture(200, &format!("http://target/{}", payload), payload, &body)
+    }
+
+    #[test]
+    fn collapses_pages_reflecting_the_payload() {
+        let deduplicator = Deduplicator::new();
+
+        assert!(!deduplicator.is_duplicate(&catch_all("backup")));
+        assert!(deduplicator.is_duplicate(&catch_all("old")));
+        // The payload also appears in the static markup, where it must be kept.
+        assert!(deduplicator.is_duplicate(&catch_all("login")));
+        assert!(deduplicator.is_duplicate(&catch_all("doc")));
+
+        let groups = deduplicator.duplicates();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].first_url, "http://target/backup");
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].examples.len(), MAX_EXAMPLES);
+
+        // Reading the groups leaves them in place.
+        assert_eq!(deduplicator.duplicates()[0].count, 3);
+        assert!(deduplicator.is_duplicate(&catch_all("new")));
+    }
+
+    #[test]
+    fn collapses_pages_after_a_payload_found_in_the_page_itself() {
+        let deduplicator = Deduplicator::new();
+
+        assert!(!deduplicator.is_duplicate(&catch_all("login")));
+        assert!(deduplicator.is_duplicate(&catch_all("backup")));
+        assert!(deduplicator.is_duplicate(&catch_all("docs")));
+        assert_eq!(deduplicator.duplicates()[0].count, 2);
+    }
+
+    #[test]
+    fn keeps_different_pages_and_statuses_apart() {
+        let deduplicator = Deduplicator::new();
+        let mut forbidden = catch_all("old");
+        forbidden.status = 403;
+
+        assert!(!deduplicator.is_duplicate(&catch_all("backup")));
+        assert!(!deduplicator.is_duplicate(&forbidden));
+        assert!(!deduplicator.is_duplicate(&FuzzResponse::fixture(200, "http://target/a", "a", "admin")));
+        assert!(deduplicator.duplicates().is_empty());
+    }
+
+    #[test]
+    fn collapses_pages_of_several_templates() {
+        let deduplicator = Deduplicator::new();
+        let search = |payload: &str| {
+            let body = format!("<h1>Search</h1> <p>No result for {} in the catalog</p>", payload);
+            FuzzResponse::fixture(200, &format!("http://target/search/{}", payload), payload, &body)
+        };
+
+        assert!(!deduplicator.is_duplicate(&catch_all("backup")));
+        assert!(!deduplicator.is_duplicate(&search("backup")));
+        assert!(deduplicator.is_duplicate(&search("old")));
+        assert!(deduplicator.is_duplicate(&catch_all("old")));
+        assert_eq!(deduplicator.duplicates().len(), 2);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::calibration::Calibration;
+use crate::filter::ResponseFilter;
+use crate::response::FuzzResponse;
+
+/// Catches responses that look like the calibrated baseline: the status and
+/// every other value learned during calibration must be equal.
+pub struct CalibrationFilter {
+    status: Option<u16>,
+    size: Option<u64>,
+    words: Option<usize>,
+    lines: Option<usize>,
+}
+
+impl CalibrationFilter {
+    pub fn new(calibration: &Calibration) -> Self {
+        Self {
+            status: calibration.status,
+            size: calibration.size,
+            words: calibration.words,
+            lines: calibration.lines,
+        }
+    }
+}
+
+impl ResponseFilter for CalibrationFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        self.status == Some(response.status)
+            && self.size.is_none_or(|size| size == response.size)
+            && self.words.is_none_or(|words| words == response.words)
+            && self.lines.is_none_or(|lines| lines == response.lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> FuzzResponse {
+        FuzzResponse::fixture(status, "http://target/page", "page", body)
+    }
+
+    fn calibration(samples: Vec<FuzzResponse>) -> Calibration {
+        Calibration::from_samples("http://target/{}", samples)
+    }
+
+    #[test]
+    fn requires_the_baseline_status() {
+        let calibration = calibration(vec![response(404, "not found"), response(404, "not found")]);
+        let filter = CalibrationFilter::new(&calibration);
+
+        assert!(calibration.is_usable());
+        assert!(filter.matches(&response(404, "not found")));
+        assert!(!filter.matches(&response(200, "not found")));
+        assert!(!filter.matches(&response(404, "gone")));
+    }
+
+    #[test]
+    fn ignores_baselines_with_an_unstable_status() {
+        let calibration = calibration(vec![response(404, "not found"), response(403, "forbidden")]);
+
+        assert_eq!(calibration.lines, Some(1));
+        assert!(!calibration.is_usable());
+        assert!(!CalibrationFilter::new(&calibration).matches(&response(200, "welcome")));
+    }
+}
@@ -0,0 +1,18 @@
+use crate::filter::ResponseFilter;
+use crate::response::FuzzResponse;
+
+pub struct ContentSizeFilter {
+    sizes: Vec<u64>,
+}
+
+impl ContentSizeFilter {
+    pub fn new(sizes: Vec<u64>) -> Self {
+        Self { sizes }
+    }
+}
+
+impl ResponseFilter for ContentSizeFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        self.sizes.contains(&response.size)
+    }
+}
@@ -0,0 +1,19 @@
+use crate::filter::ResponseFilter;
+use crate::expression::Expression;
+use crate::response::FuzzResponse;
+
+pub struct ExpressionFilter {
+    expression: Expression,
+}
+
+impl ExpressionFilter {
+    pub fn new(expression: Expression) -> Self {
+        Self { expression }
+    }
+}
+
+impl ResponseFilter for ExpressionFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        self.expression.evaluate(response)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::filter::ResponseFilter;
+use crate::response::FuzzResponse;
+use crate::similarity::Fingerprint;
+
+/// Catches responses whose body is at least `threshold` percent similar
+/// to one of the baseline responses with the same status.
+pub struct SimilarityFilter {
+    threshold: f64,
+    baselines: Vec<(u16, Fingerprint)>,
+}
+
+impl SimilarityFilter {
+    pub fn new(threshold: u8, samples: &[FuzzResponse]) -> Self {
+        let baselines = samples
+            .iter()
+            .map(|sample| (sample.status, Fingerprint::new(&sample.body, &sample.payload)))
+            .collect();
+
+        Self {
+            threshold: threshold as f64 / 100.0,
+            baselines,
+        }
+    }
+}
+
+impl ResponseFilter for SimilarityFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        let fingerprint = Fingerprint::new(&response.body, &response.payload);
+
+        self.baselines.iter().any(|(status, baseline)| {
+            *status == response.status && fingerprint.similarity(baseline) >= self.threshold
+        })
+    }
+}
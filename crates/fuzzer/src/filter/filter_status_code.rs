@@ -0,0 +1,18 @@
+use crate::filter::ResponseFilter;
+use crate::response::FuzzResponse;
+
+pub struct StatusCodeFilter {
+    codes: Vec<u16>,
+}
+
+impl StatusCodeFilter {
+    pub fn new(codes: Vec<u16>) -> Self {
+        Self { codes }
+    }
+}
+
+impl ResponseFilter for StatusCodeFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        self.codes.contains(&response.status)
+    }
+}
@@ -0,0 +1,18 @@
+use crate::filter::ResponseFilter;
+use crate::response::FuzzResponse;
+
+pub struct WordFilter {
+    words: Vec<String>,
+}
+
+impl WordFilter {
+    pub fn new(words: Vec<String>) -> Self {
+        Self { words }
+    }
+}
+
+impl ResponseFilter for WordFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool {
+        self.words.iter().any(|w| response.body.contains(w))
+    }
+}
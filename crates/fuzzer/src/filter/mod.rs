@@ -0,0 +1,261 @@
+pub mod dedupe;
+pub mod filter_calibration;
+pub mod filter_content_size;
+pub mod filter_expression;
+pub mod filter_similarity;
+pub mod filter_status_code;
+pub mod filter_word;
+
+pub use filter_calibration::CalibrationFilter;
+pub use filter_content_size::ContentSizeFilter;
+pub use filter_expression::ExpressionFilter;
+pub use filter_similarity::SimilarityFilter;
+pub use filter_status_code::StatusCodeFilter;
+pub use filter_word::WordFilter;
+
+use crate::DisplayArgs;
+use crate::filter::dedupe::{Deduplicator, DuplicateGroup, Original};
+use crate::response::FuzzResponse;
+use crate::stats::Verdict;
+
+use serde::Serialize;
+use std::{collections::HashMap, sync::RwLock};
+
+pub trait ResponseFilter {
+    fn matches(&self, response: &FuzzResponse) -> bool;
+}
+
+type Filters = Vec<Box<dyn ResponseFilter + Send + Sync>>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    And,
+    Or,
+}
+
+impl FilterMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterMode::And => "and",
+            FilterMode::Or => "or",
+        }
+    }
+
+    /// Combines the verdicts of `filters` on a response.
+    /// Returns `None` when no filter is configured.
+    pub fn evaluate(
+        &self,
+        filters: &[Box<dyn ResponseFilter + Send + Sync>],
+        response: &FuzzResponse,
+    ) -> Option<bool> {
+        if filters.is_empty() {
+            return None;
+        }
+
+        let mut verdicts = filters.iter().map(|f| f.matches(response));
+
+        match self {
+            FilterMode::And => Some(verdicts.all(|v| v)),
+            FilterMode::Or => Some(verdicts.any(|v| v)),
+        }
+    }
+}
+
+/// Matchers and filters given by the user, which can change while the scan runs.
+struct Rules {
+    matchers: Filters,
+    filters: Filters,
+    /// Required of every result, whatever the modes are.
+    expression: Option<ExpressionFilter>,
+}
+
+impl Rules {
+    fn new(args: &DisplayArgs) -> Self {
+        let mut matchers: Filters = Vec::new();
+        let mut filters: Filters = Vec::new();
+
+        if !args.matched_code.is_empty() {
+            matchers.push(Box::new(StatusCodeFilter::new(args.matched_code.clone())));
+        }
+        if !args.matched_size.is_empty() {
+            matchers.push(Box::new(ContentSizeFilter::new(args.matched_size.clone())));
+        }
+        if !args.matched_word.is_empty() {
+            matchers.push(Box::new(WordFilter::new(args.matched_word.clone())));
+        }
+
+        if !args.filtered_code.is_empty() {
+            filters.push(Box::new(StatusCodeFilter::new(args.filtered_code.clone())));
+        }
+        if !args.filtered_size.is_empty() {
+            filters.push(Box::new(ContentSizeFilter::new(args.filtered_size.clone())));
+        }
+        if !args.filtered_word.is_empty() {
+            filters.push(Box::new(WordFilter::new(args.filtered_word.clone())));
+        }
+
+        Self {
+            matchers,
+            filters,
+            expression: args.expression.clone().map(ExpressionFilter::new),
+        }
+    }
+}
+
+/// Decides which responses are results, before any output sink sees them.
+pub struct FilterStage {
+    rules: RwLock<Rules>,
+    /// Filters learned from each target, keyed by its URL template.
+    baseline_filters: HashMap<String, Filters>,
+    match_mode: FilterMode,
+    filter_mode: FilterMode,
+    deduplicator: Option<Deduplicator>,
+}
+
+impl FilterStage {
+    pub fn new(args: &DisplayArgs) -> Self {
+        let mut baseline_filters: HashMap<String, Filters> = HashMap::new();
+
+        for calibration in &args.calibrations {
+            let filters = baseline_filters.entry(calibration.target.clone()).or_default();
+
+            if calibration.is_usable() {
+                filters.push(Box::new(CalibrationFilter::new(calibration)));
+            }
+            if let Some(threshold) = args.similarity {
+                filters.push(Box::new(SimilarityFilter::new(threshold, &calibration.samples)));
+            }
+        }
+
+        Self {
+            rules: RwLock::new(Rules::new(args)),
+            baseline_filters,
+            match_mode: args.match_mode,
+            filter_mode: args.filter_mode,
+            deduplicator: args.dedupe.then(Deduplicator::new),
+        }
+    }
+
+    /// Replaces the matchers and filters with the lists of `args`, edited while the scan runs.
+    /// Baseline filters and deduplication are left as they are.
+    pub fn reload(&self, args: &DisplayArgs) {
+        *self.rules.write().unwrap() = Rules::new(args);
+    }
+
+    pub fn verdict(&self, response: &FuzzResponse) -> Verdict {
+        let rules = self.rules.read().unwrap();
+
+        // Matchers are evaluated first: a response that no matcher selects is dropped
+        // before filters get a say, whatever the filter mode is.
+        let matched = self
+            .match_mode
+            .evaluate(&rules.matchers, response)
+            .unwrap_or(true);
+
+        // The expression is its own predicate, AND'd with the matchers.
+        let selected = rules
+            .expression
+            .as_ref()
+            .is_none_or(|expression| expression.matches(response));
+
+        let filtered = self
+            .filter_mode
+            .evaluate(&rules.filters, response)
+            .unwrap_or(false);
+
+        // Baseline filters come from the target itself and always apply,
+        // independently of the filter mode.
+        let baseline = self
+            .baseline_filters
+            .get(&response.target)
+            .is_some_and(|filters| filters.iter().any(|f| f.matches(response)));
+
+        if !matched || !selected || filtered || baseline {
+            return Verdict::Filtered;
+        }
+
+        if self
+            .deduplicator
+            .as_ref()
+            .is_some_and(|deduplicator| deduplicator.is_duplicate(response))
+        {
+            return Verdict::Duplicate;
+        }
+
+        Verdict::Matched
+    }
+
+    /// Responses deduplication compares the next ones to, empty unless it is enabled.
+    pub fn originals(&self) -> Vec<Original> {
+        self.deduplicator
+            .as_ref()
+            .map(|deduplicator| deduplicator.snapshot())
+            .unwrap_or_default()
+    }
+
+    pub fn restore_originals(&self, originals: Vec<Original>) {
+        if let Some(deduplicator) = &self.deduplicator {
+            deduplicator.restore(originals);
+        }
+    }
+
+    /// Groups of collapsed responses, empty unless deduplication is enabled.
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        self.deduplicator
+            .as_ref()
+            .map(|deduplicator| deduplicator.duplicates())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expression;
+
+    fn response(status: u16, body: &str) -> FuzzResponse {
+        FuzzResponse::fixture(status, "http://target/page", "page", body)
+    }
+
+    #[test]
+    fn matchers_select_and_filters_drop() {
+        let mut args = DisplayArgs::fixture();
+        args.matched_code = vec![200, 404];
+        args.filtered_word = vec![String::from("denied")];
+        let stage = FilterStage::new(&args);
+
+        assert_eq!(stage.verdict(&response(404, "missing")), Verdict::Matched);
+        assert_eq!(stage.verdict(&response(200, "access denied")), Verdict::Filtered);
+        assert_eq!(stage.verdict(&response(500, "error")), Verdict::Filtered);
+    }
+
+    #[test]
+    fn modes_combine_the_rules() {
+        let mut args = DisplayArgs::fixture();
+        args.matched_code = vec![200];
+        args.matched_word = vec![String::from("admin")];
+        args.match_mode = FilterMode::And;
+        let stage = FilterStage::new(&args);
+
+        assert_eq!(stage.verdict(&response(200, "admin panel")), Verdict::Matched);
+        assert_eq!(stage.verdict(&response(200, "home")), Verdict::Filtered);
+
+        args.match_mode = FilterMode::Or;
+        let stage = FilterStage::new(&args);
+        assert_eq!(stage.verdict(&response(200, "home")), Verdict::Matched);
+    }
+
+    #[test]
+    fn expression_applies_on_top_of_matchers() {
+        let mut args = DisplayArgs::fixture();
+        args.matched_code = vec![200];
+        args.matched_word = vec![String::from("admin")];
+        args.expression = Some(Expression::parse("size > 10").unwrap());
+        let stage = FilterStage::new(&args);
+
+        assert_eq!(stage.verdict(&response(200, "admin panel")), Verdict::Matched);
+        assert_eq!(stage.verdict(&response(200, "tiny")), Verdict::Filtered);
+        assert_eq!(stage.verdict(&response(500, "a long error page")), Verdict::Filtered);
+    }
+}
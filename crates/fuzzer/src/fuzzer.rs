@@ -1,91 +1,541 @@
 use crate::FuzzerArgs;
+use crate::calibration::Calibration;
+use crate::checkpoint::{CHECKPOINT_INTERVAL, Checkpoint, CheckpointError, ResultLog};
+use crate::console::{Console, Pause};
 use crate::display::Display;
 use crate::DisplayArgs;
+use crate::filter::FilterStage;
+use crate::limits::{StopConditions, StopReason, StopSignal};
+use crate::links::LinkExtractor;
+use crate::output::{self, OutputSink};
+use crate::rate::RateLimiter;
+use crate::recursion::{Job, Recursion};
+use crate::response::FuzzResponse;
+use crate::scheduler::Scheduler;
+use crate::seeds::Seeds;
+use crate::stats::{ErrorKind, ScanSummary, Statistics, Verdict};
 
 use reader::{Reader, builder::ReaderBuilder};
-use sender::{Sender, builder::SenderBuilder};
+use sender::{Sender, builder::SenderBuilder, preflight::PreflightCheck};
 
-use crossbeam::thread;
-use std::sync::{Arc, Mutex};
+use colored::*;
+use crossbeam::{channel, thread};
+use std::{
+    io::{self, IsTerminal},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Longest time a worker keeps the progress of its requests before reporting it to the sinks.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct Fuzzer {
-    pub reader: Reader,
     pub sender: Sender,
-    pub display: Display,
+    filter: FilterStage,
+    recursion: Recursion,
+    /// Present when links of matched responses are requested too.
+    links: Option<LinkExtractor>,
+    sinks: Vec<Mutex<Box<dyn OutputSink>>>,
+    /// Checkpoint the scan starts from, when resuming.
+    resumed: Option<Checkpoint>,
+    /// Results not saved to the result log of the checkpoint yet.
+    results: Mutex<Vec<Arc<FuzzResponse>>>,
+    /// Result log of the checkpoint, opened by the first save unless the scan resumed from it.
+    log: Mutex<Option<ResultLog>>,
+    /// Last segments of the paths disclosed by the targets, requested by every wordlist job.
+    seed_words: Vec<String>,
+    /// Held for reading while a payload's outcome is recorded, and for writing while
+    /// a checkpoint is taken, so that a checkpoint never sees a payload half recorded.
+    commit: RwLock<()>,
+    /// Set by limits and the console to stop the scan.
+    stop: StopSignal,
+    /// Set by the owner of the scan, typically from a signal handler.
+    interrupted: Arc<AtomicBool>,
+    rate: RateLimiter,
+    /// Set while the interactive console is open.
+    pause: Pause,
+    config: DisplayArgs,
     args: FuzzerArgs,
 }
 
 impl Fuzzer {
     pub fn new(args: &FuzzerArgs) -> Self {
-        let reader = ReaderBuilder::default()
-            .with_path(args.wordlist.clone())
-            .with_threads(args.threads)
-            .build()
-            .unwrap_or_else(|e| panic!("{}", e.as_str()));
+        let reader = Self::open_wordlist(args);
+
+        let mut resumed = args.resume.as_ref().map(|path| {
+            let checkpoint = Checkpoint::load(path).unwrap_or_else(|e| panic!("{}", e.as_str()));
+            if !checkpoint.matches(&args.targets, &args.wordlist) {
+                panic!("{}", CheckpointError::Mismatch.as_str());
+            }
+            checkpoint
+        });
 
         let sender = SenderBuilder::default()
-            .with_url(args.url.clone())
+            .with_url(args.targets[0].clone())
             .with_user_agent(&args.user_agent)
+            .with_request_timeout(args.timeout)
+            .with_method(&args.method)
             .with_follow_redirect(args.follow_redirect)
             .build()
             .unwrap_or_else(|e| panic!("{}", e.as_str()));
 
-        let display = Display::new(DisplayArgs::from(args), reader.line_count);
+        // Seeding requests already go at the rate of the scan.
+        let rate = RateLimiter::new(args.rate);
+
+        if !args.skip_preflight {
+            for target in &args.targets {
+                Self::preflight(&sender, target, args.silent);
+            }
+        }
+
+        let mut display_args = DisplayArgs::from(args);
+        // The console needs a terminal to read commands from.
+        display_args.interactive = args.interactive && io::stdin().is_terminal();
+        // A resumed scan keeps the baseline it was calibrated with.
+        let calibrations = resumed.as_mut().map(|checkpoint| std::mem::take(&mut checkpoint.calibrations));
+        if args.auto_calibrate {
+            display_args.calibrations = match calibrations.filter(|calibrations| !calibrations.is_empty()) {
+                Some(calibrations) => calibrations
+                    .into_iter()
+                    .map(|(target, samples)| Calibration::from_samples(&target, samples))
+                    .collect(),
+                None => args
+                    .targets
+                    .iter()
+                    .map(|target| Calibration::run(&sender, target))
+                    .collect(),
+            };
+        }
+
+        if args.seed {
+            display_args.seeds = args.targets.iter().map(|target| Seeds::fetch(&sender, &rate, target)).collect();
+        }
+        let seed_words = Seeds::words(&display_args.seeds, &args.wordlist);
+
+        let mut sinks: Vec<Box<dyn OutputSink>> =
+            vec![Box::new(Display::new(&display_args, reader.line_count + seed_words.len()))];
+        sinks.extend(output::open_file_sinks(&display_args).unwrap_or_else(|e| panic!("{}", e.as_str())));
 
         Self {
-            reader,
             sender,
-            display,
+            filter: FilterStage::new(&display_args),
+            recursion: Recursion::new(
+                args.recursion_depth,
+                args.recursion_status.clone(),
+                args.recursion_strategy,
+                &args.targets,
+            ),
+            links: args
+                .extract_links
+                .then(|| LinkExtractor::new(args.recursion_depth.max(1))),
+            sinks: sinks.into_iter().map(Mutex::new).collect(),
+            resumed,
+            results: Mutex::new(Vec::new()),
+            log: Mutex::new(None),
+            seed_words,
+            commit: RwLock::new(()),
+            stop: StopSignal::new(),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            rate,
+            pause: Pause::new(),
+            config: display_args,
             args: args.clone(),
         }
     }
 
-    pub fn fuzz(&mut self) {
-        let reader = Arc::new(Mutex::new(&mut self.reader));
-        let sender = Arc::new(&self.sender);
-        let display = Arc::new(&self.display);
-        let url_template = Arc::new(self.sender.args.url.clone());
+    /// Registers an additional sink, notified after the built-in terminal and file sinks.
+    pub fn add_sink(&mut self, sink: Box<dyn OutputSink>) {
+        self.sinks.push(Mutex::new(sink));
+    }
+
+    /// Uses `interrupted` as the flag stopping the scan gracefully once set, typically from
+    /// a signal handler installed before the scan was set up.
+    /// The interrupted scan still flushes its outputs, reports its summary and saves a checkpoint.
+    pub fn with_interrupt_flag(mut self, interrupted: Arc<AtomicBool>) -> Self {
+        self.interrupted = interrupted;
+        self
+    }
+
+    /// Whether the scan is being stopped, by a limit, the console or an interruption.
+    fn stopping(&self) -> bool {
+        if self.interrupted.load(Ordering::Relaxed) {
+            self.stop.stop(StopReason::Interrupted);
+        }
+        self.stop.is_stopped()
+    }
+
+    /// Checks that `target` can be scanned before anything else is sent to it,
+    /// and aborts with the error of the first failing step.
+    /// Steps are printed to stderr, in silent mode only when one fails.
+    fn preflight(sender: &Sender, target: &str, silent: bool) {
+        let checks = sender.preflight(target).unwrap_or_else(|e| panic!("{}", e.as_str()));
+        let failure = checks.iter().find_map(PreflightCheck::error);
+
+        if !silent || failure.is_some() {
+            eprintln!(":: Preflight {}", target);
+            for check in &checks {
+                let outcome = match (check.passed, check.skipped) {
+                    (true, false) => "ok",
+                    (true, true) => "skipped",
+                    (false, _) => "FAILED",
+                };
+                eprintln!("   {:<12} {:<8} {}", check.step.as_str(), outcome, check.detail);
+            }
+        }
+
+        if let Some(e) = failure {
+            panic!("{}", e.as_str());
+        }
+    }
+
+    fn progress(&self, job: &Job, count: usize) {
+        if count == 0 {
+            return;
+        }
+        for sink in &self.sinks {
+            sink.lock().unwrap().on_progress(job, count);
+        }
+    }
+
+    /// Number of payloads of one wordlist job.
+    fn wordlist_size(&self) -> usize {
+        Self::open_wordlist(&self.args).line_count + self.seed_words.len()
+    }
+
+    /// Every job reads the wordlist from the start, through its own reader.
+    fn open_wordlist(args: &FuzzerArgs) -> Reader {
+        ReaderBuilder::default()
+            .with_path(args.wordlist.clone())
+            .with_threads(args.threads)
+            .build()
+            .unwrap_or_else(|e| panic!("{}", e.as_str()))
+    }
+
+    /// Queues the jobs to run: the targets, or what was left of a resumed scan.
+    /// Results of the resumed scan are handed to the sinks again.
+    fn prepare(&mut self, scheduler: &Scheduler) -> Statistics {
+        let Some(checkpoint) = self.resumed.take() else {
+            for target in &self.args.targets {
+                scheduler.push(Job::new(target), Self::open_wordlist(&self.args));
+            }
+            for seeds in self.config.seeds.iter().filter(|seeds| !seeds.paths.is_empty()) {
+                let job = seeds.job();
+                // Extracted links do not request the seeded paths again.
+                if let Some(links) = &self.links {
+                    links.restore(seeds.paths.iter().map(|path| format!("{}{}", seeds.origin, path)).collect());
+                }
+                for sink in &self.sinks {
+                    sink.lock().unwrap().on_links(&job, &seeds.paths);
+                }
+                scheduler.push_payloads(job, seeds.paths.clone());
+            }
+            return Statistics::new();
+        };
+
+        let resume = self.args.resume.as_deref().unwrap_or_default();
+        let results: Vec<Arc<FuzzResponse>> = ResultLog::read(resume, checkpoint.results)
+            .unwrap_or_else(|e| panic!("{}", e.as_str()))
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+        for response in &results {
+            for sink in &self.sinks {
+                sink.lock().unwrap().on_result(response);
+            }
+        }
+        // Saves go on with the log of the resumed checkpoint, or start a log of their own with its results.
+        match self.args.checkpoint.as_deref() == Some(resume) {
+            true => {
+                let log = ResultLog::open(resume, checkpoint.results).unwrap_or_else(|e| panic!("{}", e.as_str()));
+                *self.log.get_mut().unwrap() = Some(log);
+            }
+            false => *self.results.get_mut().unwrap() = results,
+        }
+        self.filter.restore_originals(checkpoint.originals);
+        self.recursion.restore(checkpoint.queued);
+        if let Some(links) = &self.links {
+            links.restore(checkpoint.links);
+        }
+
+        let wordlist_size = self.wordlist_size();
+        for target in &self.args.targets {
+            let job = Job::new(target);
+            if !checkpoint.jobs.iter().any(|state| state.job == job) {
+                for sink in &self.sinks {
+                    sink.lock().unwrap().on_progress(&job, wordlist_size);
+                }
+            }
+        }
+
+        for state in checkpoint.jobs {
+            for sink in &self.sinks {
+                let mut sink = sink.lock().unwrap();
+                if state.job.discovered {
+                    sink.on_links(&state.job, &state.pending);
+                } else if state.job.depth > 0 {
+                    sink.on_job(&state.job);
+                }
+                sink.on_progress(&state.job, state.completed());
+            }
+
+            if state.job.discovered {
+                scheduler.restore(state, None);
+                continue;
+            }
+
+            let mut reader = Self::open_wordlist(&self.args);
+            reader
+                .seek(state.position, state.lines_read)
+                .unwrap_or_else(|e| panic!("{}", e.as_str()));
+            scheduler.restore(state, Some(reader));
+        }
+
+        Statistics::restore(checkpoint.counters)
+    }
+
+    /// Workers are only held while the state is copied: it is written once they went on.
+    fn save_checkpoint(&self, path: &str, scheduler: &Scheduler, statistics: &Statistics) -> bool {
+        let mut log = self.log.lock().unwrap();
+
+        let (mut checkpoint, results) = {
+            let _commit = self.commit.write().unwrap();
+            let checkpoint = Checkpoint {
+                targets: self.args.targets.clone(),
+                wordlist: self.args.wordlist.clone(),
+                jobs: scheduler.snapshot(),
+                queued: self.recursion.queued(),
+                links: self.links.as_ref().map(LinkExtractor::seen).unwrap_or_default(),
+                counters: statistics.snapshot(),
+                calibrations: self
+                    .config
+                    .calibrations
+                    .iter()
+                    .map(|calibration| (calibration.target.clone(), calibration.samples.clone()))
+                    .collect(),
+                originals: self.filter.originals(),
+                results: 0,
+            };
+            (checkpoint, std::mem::take(&mut *self.results.lock().unwrap()))
+        };
+
+        let log = match log.as_mut() {
+            Some(log) => Ok(log),
+            None => ResultLog::create(path).map(|created| log.insert(created)),
+        };
+        let appended = log.and_then(|log| log.append(&results));
+        // Results the log did not take are appended by the next save.
+        if appended.is_err() {
+            self.results.lock().unwrap().splice(0..0, results);
+        }
+        let saved = appended.and_then(|count| {
+            checkpoint.results = count;
+            checkpoint.save(path)
+        });
+
+        match saved {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("{}", e.as_str().red());
+                false
+            }
+        }
+    }
+
+    pub fn fuzz(&mut self) -> ScanSummary {
+        for sink in &self.sinks {
+            sink.lock().unwrap().on_start(&self.config);
+        }
 
-        let num_threads = self.args.threads;
+        let scheduler = Scheduler::new()
+            .with_job_time_limit(self.args.limits.max_time_per_job)
+            .with_extra_payloads(self.seed_words.clone());
+        let statistics = self.prepare(&scheduler);
+        // Limits are counted from here: preflight checks, calibration and seeding are not part of the budget.
+        let conditions = StopConditions::new(self.args.limits);
 
+        let this = &*self;
+        let args = &self.args;
+        let sender = &self.sender;
+        let filter = &self.filter;
+        let recursion = &self.recursion;
+        let sinks = &self.sinks;
+        let statistics = &statistics;
+        let scheduler = &scheduler;
+        let conditions = &conditions;
+
+        // Workers hold a clone of `running` each: the checkpoint and console threads stop once they all dropped it.
+        let (running, stopped) = channel::bounded::<()>(0);
+
+        // The threads are shared by every job: the scheduler decides which job each chunk comes from.
         thread::scope(|s| {
-            for _ in 0..num_threads {
-                let reader = Arc::clone(&reader);
-                let sender = Arc::clone(&sender);
-                let display = Arc::clone(&display);
-                let url_template = Arc::clone(&url_template);
+            for _ in 0..args.threads {
+                let running = running.clone();
 
                 s.spawn(move |_| {
-                    while let Ok(mut reader) = reader.lock() {
-                        let chunk = match reader.get_next_chunk() {
-                            Ok(chunk) => chunk,
-                            Err(_) => {
-                                drop(reader);
+                    let _running = running;
+
+                    while let Some(chunk) = scheduler.next_chunk() {
+                        let job = &chunk.job;
+                        let mut interrupted = false;
+                        // Progress is reported in batches, so that sinks are not locked for every payload.
+                        let mut unreported = 0;
+                        let mut reported = Instant::now();
+
+                        'words: for word in chunk.words.iter() {
+                            this.pause.wait(|| this.stopping());
+                            // A job out of time is abandoned, its remaining payloads are skipped.
+                            if scheduler.expired(&chunk) {
+                                break;
+                            }
+                            if let Err(reason) = conditions.admit() {
+                                this.stop.stop(reason);
+                            }
+                            if this.stopping() {
+                                scheduler.stop();
+                                interrupted = true;
                                 break;
                             }
-                        };
-
-                        drop(reader);
 
-                        for word in chunk.iter() {
-                            let url = url_template.replace("{}", word).replace("\n", "");
+                            let payload = word.trim_end_matches(['\r', '\n']);
+                            let url = job.template.replace("{}", payload);
+                            let mut outcome = None;
+                            let mut last_error = None;
                             for _ in 0..3 {
+                                // A worker waiting for its slot is not held back once the scan stops.
+                                if !this.rate.wait(|| this.stopping()) {
+                                    scheduler.stop();
+                                    interrupted = true;
+                                    break 'words;
+                                }
                                 match sender.send(&url) {
-                                    Ok((response, time)) => {
-                                        display.log_response(response, time, &url);
+                                    Ok(exchange) => {
+                                        outcome = Some(FuzzResponse {
+                                            discovered: job.discovered,
+                                            ..FuzzResponse::new(exchange, &url, payload, &job.target)
+                                        });
                                         break;
                                     }
-                                    Err(_) => {
-                                        std::thread::sleep(std::time::Duration::from_millis(100));
+                                    Err(e) => {
+                                        last_error = Some(e);
+                                        std::thread::sleep(Duration::from_millis(100));
                                     }
                                 }
                             }
-                            display.increment_progress_bar();
+
+                            let _commit = this.commit.read().unwrap();
+                            if let Some(response) = outcome.map(Arc::new) {
+                                let verdict = filter.verdict(&response);
+                                // Recursion follows the status of the response, whatever the filters show.
+                                if let Some(next) = recursion.next_job(job, &response) {
+                                    for sink in sinks {
+                                        sink.lock().unwrap().on_job(&next);
+                                    }
+                                    scheduler.push(next, Self::open_wordlist(args));
+                                }
+                                if verdict == Verdict::Matched {
+                                    for sink in sinks {
+                                        sink.lock().unwrap().on_result(&response);
+                                    }
+                                    if let Some((next, paths)) =
+                                        this.links.as_ref().and_then(|links| links.next_links(job, &response))
+                                    {
+                                        for sink in sinks {
+                                            sink.lock().unwrap().on_links(&next, &paths);
+                                        }
+                                        scheduler.push_payloads(next, paths);
+                                    }
+                                }
+                                statistics.record_response(response.status, response.time, verdict);
+                                if let Some(reason) = conditions.record_response(response.status, verdict) {
+                                    this.stop.stop(reason);
+                                }
+                                // Without a checkpoint file, results are only kept in case the scan is
+                                // interrupted, and without their bodies.
+                                if verdict == Verdict::Matched {
+                                    let kept = match args.checkpoint {
+                                        Some(_) => Arc::clone(&response),
+                                        None => Arc::new(response.without_body()),
+                                    };
+                                    this.results.lock().unwrap().push(kept);
+                                }
+                            } else if let Some(e) = last_error {
+                                let kind = ErrorKind::from(&e);
+                                statistics.record_error(kind);
+                                if let Some(reason) = conditions.record_error() {
+                                    this.stop.stop(reason);
+                                }
+                                for sink in sinks {
+                                    sink.lock().unwrap().on_error(&url, kind);
+                                }
+                            }
+                            unreported += 1;
+                            if reported.elapsed() >= PROGRESS_INTERVAL {
+                                this.progress(job, std::mem::take(&mut unreported));
+                                reported = Instant::now();
+                            }
+                            scheduler.advance(&chunk);
+                        }
+                        this.progress(job, unreported);
+                        // Payloads of an interrupted chunk stay pending for the checkpoint.
+                        if !interrupted {
+                            scheduler.done(chunk.id);
                         }
                     }
                 });
             }
+            drop(running);
+
+            if this.config.interactive {
+                let console = Console {
+                    config: this.config.clone(),
+                    filter,
+                    sinks,
+                    scheduler,
+                    statistics,
+                    rate: &this.rate,
+                    pause: &this.pause,
+                    stop: &this.stop,
+                    wordlist_size: this.wordlist_size(),
+                };
+                let stopped = stopped.clone();
+                s.spawn(move |_| console.run(stopped));
+            }
+
+            if let Some(path) = &args.checkpoint {
+                s.spawn(move |_| {
+                    while stopped.recv_timeout(CHECKPOINT_INTERVAL).is_err_and(|e| e.is_timeout()) {
+                        this.save_checkpoint(path, scheduler, statistics);
+                    }
+                });
+            }
         })
         .unwrap();
+
+        let stopped = self.stop.reason();
+
+        // Without a checkpoint file, only a scan interrupted or stopped from the console is saved,
+        // to a new file of its own.
+        let checkpoint = match (&self.args.checkpoint, stopped) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Some(StopReason::Interrupted | StopReason::Stopped)) => Some(Checkpoint::default_path()),
+            (None, _) => None,
+        };
+        let saved = checkpoint.filter(|path| self.save_checkpoint(path, scheduler, statistics));
+
+        let mut summary = statistics.summary();
+        summary.duplicate_groups = self.filter.duplicates();
+        summary.expired_jobs = scheduler.expired_jobs();
+        summary.stopped = stopped;
+        if stopped.is_some() {
+            summary.checkpoint = saved;
+        }
+
+        for sink in &self.sinks {
+            sink.lock().unwrap().on_finish(&summary);
+        }
+        summary
     }
 }
@@ -1,13 +1,38 @@
+pub mod calibration;
+pub mod checkpoint;
+pub mod console;
 pub mod display;
+pub mod expression;
+pub mod filter;
 pub mod fuzzer;
+pub mod limits;
+pub mod links;
+pub mod order;
+pub mod output;
+pub mod rate;
+pub mod recursion;
+pub mod response;
+pub mod scheduler;
+pub mod seeds;
+pub mod similarity;
+pub mod stats;
+pub mod template;
 
 use crate::display::args::DisplayArgs;
+use crate::expression::Expression;
+use crate::filter::FilterMode;
+use crate::limits::Limits;
+use crate::order::ResultOrder;
+use crate::output::{Column, OutputTarget};
+use crate::recursion::RecursionStrategy;
+use crate::template::Template;
 
 #[derive(Clone)]
 pub struct FuzzerArgs {
-    pub url: String,
+    pub targets: Vec<String>,
     pub wordlist: String,
     pub threads: u8,
+    pub rate: u32,
     pub timeout: u64,
     pub user_agent: String,
     pub filtered_code: Vec<u16>,
@@ -16,16 +41,41 @@ pub struct FuzzerArgs {
     pub matched_code: Vec<u16>,
     pub matched_size: Vec<u64>,
     pub matched_word: Vec<String>,
+    pub expression: Option<Expression>,
+    pub match_mode: FilterMode,
+    pub filter_mode: FilterMode,
+    pub auto_calibrate: bool,
+    pub skip_preflight: bool,
+    pub similarity: Option<u8>,
+    pub dedupe: bool,
+    pub recursion_depth: usize,
+    pub recursion_status: Vec<u16>,
+    pub recursion_strategy: RecursionStrategy,
+    pub extract_links: bool,
+    pub seed: bool,
+    pub order: ResultOrder,
+    pub limits: Limits,
+    pub template: Option<Template>,
+    pub silent: bool,
+    pub verbose: bool,
+    pub no_color: bool,
     pub follow_redirect: bool,
+    pub outputs: Vec<OutputTarget>,
+    pub columns: Vec<Column>,
+    pub output_dir: Option<String>,
+    pub checkpoint: Option<String>,
+    pub resume: Option<String>,
+    pub interactive: bool,
     pub method: String,
 }
 
 impl From<&FuzzerArgs> for DisplayArgs {
     fn from(args: &FuzzerArgs) -> DisplayArgs {
         DisplayArgs {
-            url: args.url.clone(),
+            targets: args.targets.clone(),
             wordlist: args.wordlist.clone(),
             threads: args.threads,
+            rate: args.rate,
             timeout: args.timeout,
             user_agent: args.user_agent.clone(),
             filtered_code: args.filtered_code.clone(),
@@ -34,6 +84,29 @@ impl From<&FuzzerArgs> for DisplayArgs {
             matched_code: args.matched_code.clone(),
             matched_size: args.matched_size.clone(),
             matched_word: args.matched_word.clone(),
+            expression: args.expression.clone(),
+            match_mode: args.match_mode,
+            filter_mode: args.filter_mode,
+            calibrations: Vec::new(),
+            seeds: Vec::new(),
+            similarity: args.similarity,
+            dedupe: args.dedupe,
+            recursion_depth: args.recursion_depth,
+            recursion_status: args.recursion_status.clone(),
+            recursion_strategy: args.recursion_strategy,
+            extract_links: args.extract_links,
+            order: args.order,
+            limits: args.limits,
+            outputs: args.outputs.clone(),
+            columns: args.columns.clone(),
+            output_dir: args.output_dir.clone(),
+            checkpoint: args.checkpoint.clone(),
+            resume: args.resume.clone(),
+            interactive: args.interactive,
+            template: args.template.clone(),
+            silent: args.silent,
+            verbose: args.verbose,
+            no_color: args.no_color,
             method: args.method.clone(),
         }
     }
@@ -0,0 +1,190 @@
+use crate::stats::Verdict;
+
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Number of latest responses the share of 403 responses is measured on.
+const RECENT_RESPONSES: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Interrupted,
+    Stopped,
+    MaxTime,
+    MaxRequests,
+    Blocked,
+    Unreachable,
+    Matched,
+}
+
+impl StopReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopReason::Interrupted => "interrupted",
+            StopReason::Stopped => "stopped from the console",
+            StopReason::MaxTime => "time limit reached",
+            StopReason::MaxRequests => "request limit reached",
+            StopReason::Blocked => "too many 403 responses, the target is likely blocking the scan",
+            StopReason::Unreachable => "every request failed for too long",
+            StopReason::Matched => "first match found",
+        }
+    }
+}
+
+/// Budgets of a scan and conditions ending it early.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize)]
+pub struct Limits {
+    pub max_time: Option<Duration>,
+    pub max_requests: Option<usize>,
+    /// Time after which a job is abandoned, the other jobs going on.
+    pub max_time_per_job: Option<Duration>,
+    /// Share of 403 among the recent responses above which the scan stops.
+    pub blocked_percent: Option<u8>,
+    /// Time without any response after which the scan stops, when requests keep failing.
+    pub error_time: Option<Duration>,
+    pub stop_on_match: bool,
+}
+
+impl Limits {
+    pub fn is_active(&self) -> bool {
+        *self != Limits::default()
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+
+        if let Some(max_time) = self.max_time {
+            parts.push(format!("max {}s", max_time.as_secs()));
+        }
+        if let Some(max_requests) = self.max_requests {
+            parts.push(format!("max {} requests", max_requests));
+        }
+        if let Some(max_time_per_job) = self.max_time_per_job {
+            parts.push(format!("max {}s per job", max_time_per_job.as_secs()));
+        }
+        if let Some(percent) = self.blocked_percent {
+            parts.push(format!("stop on {}% of 403", percent));
+        }
+        if let Some(error_time) = self.error_time {
+            parts.push(format!("stop on {}s of errors", error_time.as_secs()));
+        }
+        if self.stop_on_match {
+            parts.push(String::from("stop on first match"));
+        }
+
+        parts.join(", ")
+    }
+}
+
+/// Set once a running scan has to stop: workers finish their current request and the scan
+/// ends as usual. The first reason given is kept.
+pub struct StopSignal {
+    /// Read by the workers before every request: the reason is only locked to stop the scan.
+    stopped: AtomicBool,
+    reason: Mutex<Option<StopReason>>,
+}
+
+impl StopSignal {
+    pub fn new() -> Self {
+        Self {
+            stopped: AtomicBool::new(false),
+            reason: Mutex::new(None),
+        }
+    }
+
+    pub fn stop(&self, reason: StopReason) {
+        self.reason.lock().unwrap().get_or_insert(reason);
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Why the scan was stopped, `None` while it runs.
+    pub fn reason(&self) -> Option<StopReason> {
+        *self.reason.lock().unwrap()
+    }
+}
+
+impl Default for StopSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Recent {
+    /// Whether each of the latest responses was a 403.
+    forbidden: VecDeque<bool>,
+    last_response: Instant,
+}
+
+/// Checks the limits of a running scan.
+/// Budgets apply to the current run, not to the scan it may resume.
+pub struct StopConditions {
+    limits: Limits,
+    started: Instant,
+    sent: AtomicUsize,
+    recent: Mutex<Recent>,
+}
+
+impl StopConditions {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            started: Instant::now(),
+            sent: AtomicUsize::new(0),
+            recent: Mutex::new(Recent {
+                forbidden: VecDeque::with_capacity(RECENT_RESPONSES),
+                last_response: Instant::now(),
+            }),
+        }
+    }
+
+    /// Called before each request: counts it, unless a budget is spent.
+    pub fn admit(&self) -> Result<(), StopReason> {
+        if self.limits.max_time.is_some_and(|max_time| self.started.elapsed() >= max_time) {
+            return Err(StopReason::MaxTime);
+        }
+
+        let sent = self.sent.fetch_add(1, Ordering::Relaxed);
+        if self.limits.max_requests.is_some_and(|max_requests| sent >= max_requests) {
+            return Err(StopReason::MaxRequests);
+        }
+
+        Ok(())
+    }
+
+    pub fn record_response(&self, status: u16, verdict: Verdict) -> Option<StopReason> {
+        if self.limits.stop_on_match && verdict == Verdict::Matched {
+            return Some(StopReason::Matched);
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        recent.last_response = Instant::now();
+
+        let percent = self.limits.blocked_percent?;
+        if recent.forbidden.len() == RECENT_RESPONSES {
+            recent.forbidden.pop_front();
+        }
+        recent.forbidden.push_back(status == 403);
+
+        let forbidden = recent.forbidden.iter().filter(|&&forbidden| forbidden).count();
+        let blocked = recent.forbidden.len() == RECENT_RESPONSES && forbidden * 100 > percent as usize * RECENT_RESPONSES;
+        blocked.then_some(StopReason::Blocked)
+    }
+
+    pub fn record_error(&self) -> Option<StopReason> {
+        let error_time = self.limits.error_time?;
+        let recent = self.recent.lock().unwrap();
+        (recent.last_response.elapsed() >= error_time).then_some(StopReason::Unreachable)
+    }
+}
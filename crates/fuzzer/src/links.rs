@@ -0,0 +1,159 @@
+use crate::recursion::Job;
+use crate::response::FuzzResponse;
+
+use regex::Regex;
+use reqwest::Url;
+use std::{collections::HashSet, sync::Mutex};
+
+/// Finds the links of matched HTML responses, to request the paths the wordlist missed.
+/// Following a link is one level deeper than the job that found it, as a recursive scan is.
+pub struct LinkExtractor {
+    /// Depth of the jobs whose responses are no longer searched for links.
+    max_depth: usize,
+    attributes: Regex,
+    scripts: Regex,
+    literals: Regex,
+    /// Every URL queued so far, so that each is requested once.
+    seen: Mutex<HashSet<String>>,
+}
+
+impl LinkExtractor {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            attributes: Regex::new(r#"(?i)\b(?:href|src|action)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap(),
+            scripts: Regex::new(r"(?is)<script[^>]*>(.*?)</script>").unwrap(),
+            // Quoted absolute URLs and absolute paths, as inline scripts build requests from them.
+            literals: Regex::new(r#"["'`]((?:https?://|/)[^"'`\s<>]*)["'`]"#).unwrap(),
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the job requesting the new in-scope links of `response`, found by `job`, with
+    /// their paths as payloads. Links are in scope when they share the origin of the target.
+    pub fn next_links(&self, job: &Job, response: &FuzzResponse) -> Option<(Job, Vec<String>)> {
+        if job.depth >= self.max_depth {
+            return None;
+        }
+        if !response.content_type().is_some_and(|content_type| content_type.contains("html")) {
+            return None;
+        }
+
+        let base = Url::parse(&response.url).ok()?;
+        let origin = Url::parse(&job.target.replace("{}", "")).ok()?.origin();
+
+        let mut links: Vec<&str> = Vec::new();
+        for captures in self.attributes.captures_iter(&response.body) {
+            links.extend((1..=3).find_map(|i| captures.get(i)).map(|link| link.as_str()));
+        }
+        for script in self.scripts.captures_iter(&response.body) {
+            let script = script.get(1).map_or("", |script| script.as_str());
+            links.extend(self.literals.captures_iter(script).filter_map(|literal| literal.get(1)).map(|link| link.as_str()));
+        }
+
+        let mut seen = self.seen.lock().unwrap();
+        let mut paths = Vec::new();
+
+        for link in links {
+            let Ok(mut url) = base.join(link.trim()) else { continue };
+            url.set_fragment(None);
+            if url.origin() != origin || url.as_str() == response.url {
+                continue;
+            }
+
+            if seen.insert(url.to_string()) {
+                let path = match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                };
+                paths.push(path);
+            }
+        }
+
+        if paths.is_empty() {
+            return None;
+        }
+
+        let job = Job {
+            target: job.target.clone(),
+            template: format!("{}{{}}", origin.ascii_serialization()),
+            depth: job.depth + 1,
+            discovered: true,
+        };
+        Some((job, paths))
+    }
+
+    /// URLs queued so far, to save in checkpoints.
+    pub fn seen(&self) -> Vec<String> {
+        self.seen.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Marks the links of a resumed scan as queued, so they are not requested twice.
+    pub fn restore(&self, urls: Vec<String>) {
+        self.seen.lock().unwrap().extend(urls);
+    }
+}
+
+impl Default for LinkExtractor {
+    /// Follows the links of wordlist results only.
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, body: &str) -> FuzzResponse {
+        let mut response = FuzzResponse::fixture(200, url, "index.html", body);
+        response.headers.insert("content-type", "text/html; charset=utf-8".parse().unwrap());
+        response
+    }
+
+    const BODY: &str = r#"<a href="/admin/">Admin</a> <img src='static/logo.png'> <form action=login.php>
+        <a href="https://other.site/page">Other</a> <a href="http://target:8080/port">Port</a>
+        <a href="page.html#top">Top</a> <a href="/index.html">Home</a>
+        <script>fetch("/api/users?limit=10"); const cdn = 'https://cdn.site/lib.js';</script>"#;
+
+    #[test]
+    fn extracts_attributes_and_script_paths_of_the_target_origin() {
+        let extractor = LinkExtractor::default();
+        let job = Job::new("http://target/{}");
+
+        let (next, paths) = extractor.next_links(&job, &page("http://target/index.html", BODY)).unwrap();
+        assert_eq!(paths, vec!["/admin/", "/static/logo.png", "/login.php", "/page.html", "/api/users?limit=10"]);
+        assert_eq!(next.template, "http://target{}");
+        assert_eq!(next.depth, 1);
+        assert!(next.discovered);
+    }
+
+    #[test]
+    fn queues_each_link_once() {
+        let extractor = LinkExtractor::default();
+        let job = Job::new("http://target/{}");
+
+        assert!(extractor.next_links(&job, &page("http://target/index.html", BODY)).is_some());
+        // The first page did not queue itself.
+        let (_, paths) = extractor.next_links(&job, &page("http://target/copy.html", BODY)).unwrap();
+        assert_eq!(paths, vec!["/index.html"]);
+        assert!(extractor.next_links(&job, &page("http://target/index.html", BODY)).is_none());
+    }
+
+    #[test]
+    fn stops_at_the_depth_and_on_other_content() {
+        let extractor = LinkExtractor::new(2);
+        let mut job = Job::new("http://target/{}");
+        let body = r#"<a href="/next">Next</a>"#;
+
+        let mut text = page("http://target/notes.txt", body);
+        text.headers.insert("content-type", "text/plain".parse().unwrap());
+        assert!(extractor.next_links(&job, &text).is_none());
+
+        job.depth = 2;
+        assert!(extractor.next_links(&job, &page("http://target/deep.html", body)).is_none());
+        job.depth = 1;
+        let (next, _) = extractor.next_links(&job, &page("http://target/deep.html", body)).unwrap();
+        assert_eq!(next.depth, 2);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::response::FuzzResponse;
+
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    Status,
+    Size,
+    Url,
+    Time,
+}
+
+impl SortKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Status => "status",
+            SortKey::Size => "size",
+            SortKey::Url => "url",
+            SortKey::Time => "time",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupKey {
+    Status,
+    Size,
+}
+
+impl GroupKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupKey::Status => "status",
+            GroupKey::Size => "size",
+        }
+    }
+
+    fn value(&self, response: &FuzzResponse) -> u64 {
+        match self {
+            GroupKey::Status => response.status as u64,
+            GroupKey::Size => response.size,
+        }
+    }
+
+    fn label(&self, value: u64) -> String {
+        match self {
+            GroupKey::Status => format!("Status {}", value),
+            GroupKey::Size => format!("Size {}", value),
+        }
+    }
+}
+
+/// Results sharing the same group key. `label` is `None` when results are not grouped.
+pub struct ResultGroup {
+    pub label: Option<String>,
+    pub responses: Vec<Arc<FuzzResponse>>,
+}
+
+/// How results are re-rendered once the scan is over.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize)]
+pub struct ResultOrder {
+    pub sort: Option<SortKey>,
+    pub group_by: Option<GroupKey>,
+}
+
+impl ResultOrder {
+    pub fn is_active(&self) -> bool {
+        self.sort.is_some() || self.group_by.is_some()
+    }
+
+    /// Sorts `responses` and splits them into groups.
+    ///
+    /// Status groups come in ascending order. Size groups come from the rarest to the most
+    /// common size, so that outliers stand out before the pages the target serves everywhere.
+    pub fn arrange(&self, mut responses: Vec<Arc<FuzzResponse>>) -> Vec<ResultGroup> {
+        // Ties are broken on the URL so that the order does not depend on thread scheduling.
+        responses.sort_by(|a, b| a.url.cmp(&b.url));
+        match self.sort {
+            Some(SortKey::Status) => responses.sort_by_key(|r| r.status),
+            Some(SortKey::Size) => responses.sort_by_key(|r| r.size),
+            Some(SortKey::Time) => responses.sort_by_key(|r| r.time),
+            Some(SortKey::Url) | None => {}
+        }
+
+        let Some(group_by) = self.group_by else {
+            return vec![ResultGroup {
+                label: None,
+                responses,
+            }];
+        };
+
+        let mut groups: BTreeMap<u64, Vec<Arc<FuzzResponse>>> = BTreeMap::new();
+        for response in responses {
+            groups.entry(group_by.value(&response)).or_default().push(response);
+        }
+
+        let mut groups: Vec<(u64, Vec<Arc<FuzzResponse>>)> = groups.into_iter().collect();
+        if group_by == GroupKey::Size {
+            groups.sort_by_key(|(_, responses)| responses.len());
+        }
+
+        groups
+            .into_iter()
+            .map(|(value, responses)| ResultGroup {
+                label: Some(group_by.label(value)),
+                responses,
+            })
+            .collect()
+    }
+
+    pub fn describe(&self) -> String {
+        match (self.sort, self.group_by) {
+            (Some(sort), Some(group_by)) => {
+                format!("sorted by {}, grouped by {}", sort.as_str(), group_by.as_str())
+            }
+            (Some(sort), None) => format!("sorted by {}", sort.as_str()),
+            (None, Some(group_by)) => format!("grouped by {}", group_by.as_str()),
+            (None, None) => String::from("completion order"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(status: u16, payload: &str, body: &str) -> Arc<FuzzResponse> {
+        Arc::new(FuzzResponse::fixture(status, &format!("http://target/{}", payload), payload, body))
+    }
+
+    fn results() -> Vec<Arc<FuzzResponse>> {
+        vec![
+            result(403, "c", "forbidden"),
+            result(200, "b", "home"),
+            result(200, "a", "home"),
+            result(301, "d", ""),
+        ]
+    }
+
+    fn payloads(group: &ResultGroup) -> Vec<&str> {
+        group.responses.iter().map(|response| response.payload.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_with_ties_broken_on_the_url() {
+        let order = ResultOrder {
+            sort: Some(SortKey::Status),
+            group_by: None,
+        };
+        let groups = order.arrange(results());
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].label, None);
+        assert_eq!(payloads(&groups[0]), vec!["a", "b", "d", "c"]);
+
+        let order = ResultOrder {
+            sort: Some(SortKey::Size),
+            group_by: None,
+        };
+        assert_eq!(payloads(&order.arrange(results())[0]), vec!["d", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn groups_statuses_in_ascending_order() {
+        let order = ResultOrder {
+            sort: Some(SortKey::Url),
+            group_by: Some(GroupKey::Status),
+        };
+        let groups = order.arrange(results());
+
+        let labels: Vec<&str> = groups.iter().filter_map(|group| group.label.as_deref()).collect();
+        assert_eq!(labels, vec!["Status 200", "Status 301", "Status 403"]);
+        assert_eq!(payloads(&groups[0]), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn groups_the_rarest_sizes_first() {
+        let order = ResultOrder {
+            sort: None,
+            group_by: Some(GroupKey::Size),
+        };
+        let groups = order.arrange(results());
+
+        let labels: Vec<&str> = groups.iter().filter_map(|group| group.label.as_deref()).collect();
+        assert_eq!(labels, vec!["Size 0", "Size 9", "Size 4"]);
+    }
+}
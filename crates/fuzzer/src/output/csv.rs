@@ -0,0 +1,52 @@
+use crate::DisplayArgs;
+use crate::output::{Column, OutputError, ResultRecord, ResultWriter};
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+pub struct CsvWriter {
+    writer: BufWriter<File>,
+    columns: Vec<Column>,
+}
+
+impl CsvWriter {
+    pub fn create(path: &str, columns: &[Column]) -> Result<Self, OutputError> {
+        let file = File::create(path).map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            columns: columns.to_vec(),
+        })
+    }
+
+    fn write_row(&mut self, cells: Vec<String>) -> Result<(), OutputError> {
+        let row: Vec<String> = cells.iter().map(|cell| escape(cell)).collect();
+        writeln!(self.writer, "{}", row.join(",")).map_err(|_| OutputError::WriteFailed)
+    }
+}
+
+impl ResultWriter for CsvWriter {
+    fn write_header(&mut self, _args: &DisplayArgs) -> Result<(), OutputError> {
+        let names = self.columns.iter().map(|c| c.as_str().to_string()).collect();
+        self.write_row(names)
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        let cells = self.columns.iter().map(|c| c.value(record)).collect();
+        self.write_row(cells)
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        self.writer.flush().map_err(|_| OutputError::WriteFailed)
+    }
+}
+
+/// Quotes a cell when it holds a separator, a quote or a line break (RFC 4180).
+fn escape(cell: &str) -> String {
+    match cell.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::DisplayArgs;
+use crate::output::{OutputError, ResultRecord, ResultWriter};
+
+use reqwest::{StatusCode, Url, Version, header::HeaderMap};
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Stores the raw request and response of every result in its own file,
+/// named after a hash of the method and URL, and lists them in `index.txt`.
+/// The directory is added to, so that a resumed scan keeps what was stored before.
+pub struct EvidenceWriter {
+    directory: PathBuf,
+    index: BufWriter<File>,
+    /// Lines of `index.txt`, which results handed again on resume are not listed twice in.
+    listed: HashSet<String>,
+}
+
+impl EvidenceWriter {
+    pub fn create(directory: &str) -> Result<Self, OutputError> {
+        let directory = PathBuf::from(directory);
+        fs::create_dir_all(&directory).map_err(|_| OutputError::DirectoryNotCreated)?;
+
+        let path = directory.join("index.txt");
+        let listed = fs::read_to_string(&path)
+            .map(|index| index.lines().map(String::from).collect())
+            .unwrap_or_default();
+        let index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            directory,
+            index: BufWriter::new(index),
+            listed,
+        })
+    }
+
+    /// FNV-1a hash of the method and URL, which stays the same across builds
+    /// for the files of a resumed scan to be found again.
+    fn file_name(record: &ResultRecord) -> String {
+        let hash = [record.method.as_bytes(), b" ", record.url.as_bytes()]
+            .concat()
+            .iter()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME));
+        format!("{:016x}.txt", hash)
+    }
+
+    /// Rebuilds the request as it was sent: the method, the target, the `Host` header
+    /// the connection adds, and the headers the request was built with.
+    fn raw_request(record: &ResultRecord) -> Vec<u8> {
+        let (target, host) = match Url::parse(record.url) {
+            Ok(url) => {
+                let mut target = url.path().to_string();
+                if let Some(query) = url.query() {
+                    target.push('?');
+                    target.push_str(query);
+                }
+                let host = match url.port() {
+                    Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                    None => url.host_str().unwrap_or_default().to_string(),
+                };
+                (target, host)
+            }
+            Err(_) => (record.url.to_string(), String::new()),
+        };
+
+        // The client speaks HTTP/1.1 unless HTTP/2 was negotiated, whatever version the server answered with.
+        let version = match record.response.version {
+            Version::HTTP_2 => Version::HTTP_2,
+            _ => Version::HTTP_11,
+        };
+
+        let mut raw = format!("{} {} {:?}\r\nHost: {}\r\n", record.method, target, version, host).into_bytes();
+        Self::push_headers(&mut raw, &record.response.request_headers);
+        raw
+    }
+
+    fn raw_response(record: &ResultRecord) -> Vec<u8> {
+        let reason = StatusCode::from_u16(record.status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default();
+
+        let mut raw = format!("{:?} {} {}\r\n", record.response.version, record.status, reason).into_bytes();
+        Self::push_headers(&mut raw, &record.response.headers);
+        raw.extend_from_slice(record.response.raw_body());
+        raw
+    }
+
+    /// Appends the header lines and the blank line ending them, values left as received.
+    fn push_headers(raw: &mut Vec<u8>, headers: &HeaderMap) {
+        for (name, value) in headers {
+            raw.extend_from_slice(name.as_str().as_bytes());
+            raw.extend_from_slice(b": ");
+            raw.extend_from_slice(value.as_bytes());
+            raw.extend_from_slice(b"\r\n");
+        }
+        raw.extend_from_slice(b"\r\n");
+    }
+}
+
+impl ResultWriter for EvidenceWriter {
+    fn write_header(&mut self, _args: &DisplayArgs) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        let name = Self::file_name(record);
+        let path = self.directory.join(&name);
+
+        // A result restored without its body keeps the evidence written when it was found.
+        if !(record.response.stripped && path.exists()) {
+            let mut content = Self::raw_request(record);
+            content.extend(Self::raw_response(record));
+            fs::write(path, content).map_err(|_| OutputError::WriteFailed)?;
+        }
+
+        let line = format!("{}\t{}\t{}\t{}", name, record.status, record.method, record.url);
+        if !self.listed.insert(line.clone()) {
+            return Ok(());
+        }
+        writeln!(self.index, "{}", line).map_err(|_| OutputError::WriteFailed)
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        self.index.flush().map_err(|_| OutputError::WriteFailed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::FuzzResponse;
+
+    fn ok<T>(result: Result<T, OutputError>) -> T {
+        result.unwrap_or_else(|e| panic!("{}", e.as_str()))
+    }
+
+    #[test]
+    fn names_files_with_a_stable_hash() {
+        let response = FuzzResponse::fixture(200, "http://target/admin", "admin", "<p>admin</p>");
+        assert_eq!(EvidenceWriter::file_name(&ResultRecord::new(&response, "GET")), "92d1ba4015fdb7d2.txt");
+    }
+
+    #[test]
+    fn adds_to_the_index_of_a_resumed_scan() {
+        let directory = std::env::temp_dir().join(format!("rake-{}-evidence", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let directory = directory.to_string_lossy().into_owned();
+        let admin = FuzzResponse::fixture(200, "http://target/admin", "admin", "<p>admin</p>");
+        let backup = FuzzResponse::fixture(200, "http://target/backup", "backup", "<p>backup</p>");
+
+        let mut writer = ok(EvidenceWriter::create(&directory));
+        ok(writer.write_record(&ResultRecord::new(&admin, "GET")));
+        ok(writer.finish());
+
+        // The resumed scan hands its previous results again before the new ones.
+        let mut writer = ok(EvidenceWriter::create(&directory));
+        ok(writer.write_record(&ResultRecord::new(&admin.without_body(), "GET")));
+        ok(writer.write_record(&ResultRecord::new(&backup, "GET")));
+        ok(writer.finish());
+
+        let index = fs::read_to_string(PathBuf::from(&directory).join("index.txt")).unwrap();
+        let urls: Vec<&str> = index.lines().filter_map(|line| line.split('\t').nth(3)).collect();
+        assert_eq!(urls, vec!["http://target/admin", "http://target/backup"]);
+
+        let admin_file = PathBuf::from(&directory).join("92d1ba4015fdb7d2.txt");
+        assert!(fs::read_to_string(admin_file).unwrap().ends_with("<p>admin</p>"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+use crate::DisplayArgs;
+use crate::output::{OutputError, ResultRecord, ResultWriter};
+
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Number of body characters kept for each response preview.
+const PREVIEW_MAX_CHARS: usize = 4096;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+table { border-collapse: collapse; width: 100%; margin: 1em 0; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; font-size: 0.9em; }
+th { background: #f4f4f4; }
+#results th { cursor: pointer; user-select: none; }
+#results th::after { content: " \2195"; color: #aaa; }
+#config td:first-child { font-weight: bold; width: 12em; }
+#filter { padding: 6px; width: 30em; }
+pre { white-space: pre-wrap; word-break: break-all; max-height: 30em; overflow: auto; background: #fafafa; padding: 0.5em; }
+.s2 { color: #1a7f37; } .s3 { color: #0969da; } .s4 { color: #9a6700; } .s5 { color: #cf222e; }
+.chart rect.s2 { fill: #2da44e; } .chart rect.s3 { fill: #218bff; } .chart rect.s4 { fill: #d4a72c; }
+.chart rect.s5 { fill: #e5534b; } .chart rect.s1 { fill: #999; }
+.chart text { font-size: 12px; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll('#results th').forEach(function (th, index) {
+  var ascending = true;
+  th.addEventListener('click', function () {
+    var body = document.querySelector('#results tbody');
+    var rows = Array.from(body.querySelectorAll('tr.result'));
+    rows.sort(function (a, b) {
+      var x = a.children[index].dataset.sort, y = b.children[index].dataset.sort;
+      var nx = parseFloat(x), ny = parseFloat(y);
+      var order = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+      return ascending ? order : -order;
+    });
+    ascending = !ascending;
+    rows.forEach(function (row) {
+      var preview = row.nextElementSibling;
+      body.appendChild(row);
+      body.appendChild(preview);
+    });
+  });
+});
+document.getElementById('filter').addEventListener('input', function (event) {
+  var needle = event.target.value.toLowerCase();
+  document.querySelectorAll('#results tr.result').forEach(function (row) {
+    var visible = row.textContent.toLowerCase().includes(needle);
+    row.style.display = visible ? '' : 'none';
+    row.nextElementSibling.style.display = visible ? '' : 'none';
+  });
+});
+"#;
+
+struct Row {
+    url: String,
+    payload: String,
+    status: u16,
+    size: u64,
+    words: usize,
+    lines: usize,
+    duration_ms: u64,
+    redirect: String,
+    content_type: String,
+    preview: String,
+}
+
+/// Writes a self-contained HTML report once the scan finishes.
+pub struct HtmlWriter {
+    writer: BufWriter<File>,
+    config: Vec<(String, String)>,
+    target: String,
+    rows: Vec<Row>,
+}
+
+impl HtmlWriter {
+    pub fn create(path: &str) -> Result<Self, OutputError> {
+        let file = File::create(path).map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            config: Vec::new(),
+            target: String::new(),
+            rows: Vec::new(),
+        })
+    }
+
+    fn write_report(&mut self) -> std::io::Result<()> {
+        writeln!(self.writer, "<!DOCTYPE html>")?;
+        writeln!(self.writer, "<html lang=\"en\">")?;
+        writeln!(self.writer, "<head>")?;
+        writeln!(self.writer, "<meta charset=\"utf-8\">")?;
+        writeln!(self.writer, "<title>rake report - {}</title>", escape(&self.target))?;
+        writeln!(self.writer, "<style>{}</style>", STYLE)?;
+        writeln!(self.writer, "</head>")?;
+        writeln!(self.writer, "<body>")?;
+        writeln!(self.writer, "<h1>rake report</h1>")?;
+        writeln!(self.writer, "<p><code>{}</code></p>", escape(&self.target))?;
+
+        self.write_config()?;
+        self.write_chart()?;
+        self.write_results()?;
+
+        writeln!(self.writer, "<script>{}</script>", SCRIPT)?;
+        writeln!(self.writer, "</body>")?;
+        writeln!(self.writer, "</html>")?;
+
+        self.writer.flush()
+    }
+
+    fn write_config(&mut self) -> std::io::Result<()> {
+        writeln!(self.writer, "<h2>Configuration</h2>")?;
+        writeln!(self.writer, "<table id=\"config\">")?;
+        for (key, value) in &self.config {
+            writeln!(
+                self.writer,
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape(key),
+                escape(value)
+            )?;
+        }
+        writeln!(self.writer, "</table>")
+    }
+
+    /// Horizontal bar chart of the number of results per status code, as inline SVG.
+    fn write_chart(&mut self) -> std::io::Result<()> {
+        let mut distribution: BTreeMap<u16, usize> = BTreeMap::new();
+        for row in &self.rows {
+            *distribution.entry(row.status).or_default() += 1;
+        }
+
+        writeln!(self.writer, "<h2>Status distribution</h2>")?;
+
+        let max = distribution.values().copied().max().unwrap_or(1);
+        let bar_height = 22;
+        let height = distribution.len() * bar_height + 4;
+
+        writeln!(
+            self.writer,
+            "<svg class=\"chart\" width=\"640\" height=\"{}\" role=\"img\">",
+            height
+        )?;
+        for (i, (status, count)) in distribution.iter().enumerate() {
+            let y = i * bar_height;
+            let width = (*count as f64 / max as f64 * 480.0).max(2.0);
+            writeln!(
+                self.writer,
+                "<text x=\"0\" y=\"{}\">{}</text><rect class=\"s{}\" x=\"48\" y=\"{}\" width=\"{:.0}\" height=\"{}\"/><text x=\"{:.0}\" y=\"{}\">{}</text>",
+                y + 15,
+                status,
+                status / 100,
+                y + 2,
+                width,
+                bar_height - 6,
+                width + 56.0,
+                y + 15,
+                count
+            )?;
+        }
+        writeln!(self.writer, "</svg>")
+    }
+
+    fn write_results(&mut self) -> std::io::Result<()> {
+        writeln!(self.writer, "<h2>Results ({})</h2>", self.rows.len())?;
+        writeln!(
+            self.writer,
+            "<input id=\"filter\" type=\"search\" placeholder=\"Filter results...\">"
+        )?;
+        writeln!(self.writer, "<table id=\"results\">")?;
+        writeln!(
+            self.writer,
+            "<thead><tr><th>Status</th><th>URL</th><th>Payload</th><th>Size</th><th>Words</th><th>Lines</th><th>Time (ms)</th><th>Redirect</th><th>Content-Type</th></tr></thead>"
+        )?;
+        writeln!(self.writer, "<tbody>")?;
+
+        for row in &self.rows {
+            writeln!(
+                self.writer,
+                "<tr class=\"result\"><td class=\"s{}\" data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td></tr>",
+                row.status / 100,
+                row.status,
+                row.status,
+                escape(&row.url),
+                escape(&row.url),
+                escape(&row.payload),
+                escape(&row.payload),
+                row.size,
+                row.size,
+                row.words,
+                row.words,
+                row.lines,
+                row.lines,
+                row.duration_ms,
+                row.duration_ms,
+                escape(&row.redirect),
+                escape(&row.redirect),
+                escape(&row.content_type),
+                escape(&row.content_type),
+            )?;
+            writeln!(
+                self.writer,
+                "<tr class=\"preview\"><td colspan=\"9\"><details><summary>Response preview</summary><pre>{}</pre></details></td></tr>",
+                escape(&row.preview)
+            )?;
+        }
+
+        writeln!(self.writer, "</tbody>")?;
+        writeln!(self.writer, "</table>")
+    }
+}
+
+impl ResultWriter for HtmlWriter {
+    fn write_header(&mut self, args: &DisplayArgs) -> Result<(), OutputError> {
+        let config = serde_json::to_value(args).map_err(|_| OutputError::WriteFailed)?;
+
+        if let Value::Object(map) = config {
+            self.config = map
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(s) => s,
+                        Value::Null => String::from("-"),
+                        other => other.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect();
+        }
+
+        self.target = args.targets.join(", ");
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        self.rows.push(Row {
+            url: record.url.to_string(),
+            payload: record.payload.to_string(),
+            status: record.status,
+            size: record.size,
+            words: record.words,
+            lines: record.lines,
+            duration_ms: record.duration_ms,
+            redirect: record.redirect.unwrap_or_default().to_string(),
+            content_type: record.content_type.unwrap_or_default().to_string(),
+            preview: record.response.body.chars().take(PREVIEW_MAX_CHARS).collect(),
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        self.write_report().map_err(|_| OutputError::WriteFailed)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,69 @@
+use crate::DisplayArgs;
+use crate::output::{OutputError, ResultRecord, ResultWriter};
+
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Streams results to a JSON document or a JSON Lines file.
+///
+/// JSON output is `{"config": {...}, "results": [...]}`; JSON Lines output starts
+/// with a `{"config": {...}}` line followed by one result per line.
+pub struct JsonWriter {
+    writer: BufWriter<File>,
+    lines: bool,
+    records: usize,
+}
+
+impl JsonWriter {
+    pub fn create(path: &str, lines: bool) -> Result<Self, OutputError> {
+        let file = File::create(path).map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            lines,
+            records: 0,
+        })
+    }
+
+}
+
+impl ResultWriter for JsonWriter {
+    fn write_header(&mut self, args: &DisplayArgs) -> Result<(), OutputError> {
+        let config = json!({ "config": args }).to_string();
+
+        match self.lines {
+            true => writeln!(self.writer, "{}", config),
+            false => {
+                // Leave the object open so results can be streamed into the array.
+                let open = &config[..config.len() - 1];
+                write!(self.writer, "{},\"results\":[", open)
+            }
+        }
+        .map_err(|_| OutputError::WriteFailed)
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        let line = serde_json::to_string(record).map_err(|_| OutputError::WriteFailed)?;
+
+        match (self.lines, self.records) {
+            (true, _) => writeln!(self.writer, "{}", line),
+            (false, 0) => write!(self.writer, "{}", line),
+            (false, _) => write!(self.writer, ",{}", line),
+        }
+        .map_err(|_| OutputError::WriteFailed)?;
+
+        self.records += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        if !self.lines {
+            writeln!(self.writer, "]}}").map_err(|_| OutputError::WriteFailed)?;
+        }
+
+        self.writer.flush().map_err(|_| OutputError::WriteFailed)
+    }
+}
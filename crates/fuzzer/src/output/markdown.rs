@@ -0,0 +1,81 @@
+use crate::DisplayArgs;
+use crate::output::{Column, OutputError, ResultRecord, ResultWriter};
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Writes a Markdown report with one table per status class.
+/// Rows are kept in memory until the scan finishes.
+pub struct MarkdownWriter {
+    writer: BufWriter<File>,
+    columns: Vec<Column>,
+    groups: BTreeMap<u16, Vec<Vec<String>>>,
+}
+
+impl MarkdownWriter {
+    pub fn create(path: &str, columns: &[Column]) -> Result<Self, OutputError> {
+        let file = File::create(path).map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            columns: columns.to_vec(),
+            groups: BTreeMap::new(),
+        })
+    }
+
+    fn write_intro(&mut self, args: &DisplayArgs) -> std::io::Result<()> {
+        writeln!(self.writer, "# rake results")?;
+        writeln!(self.writer)?;
+        for target in &args.targets {
+            writeln!(self.writer, "- **URL**: `{}`", target)?;
+        }
+        writeln!(self.writer, "- **Wordlist**: `{}`", args.wordlist)?;
+        writeln!(self.writer, "- **Method**: {}", args.method)?;
+        writeln!(self.writer)
+    }
+
+    fn write_groups(&mut self) -> std::io::Result<()> {
+        let header: Vec<&str> = self.columns.iter().map(|c| c.as_str()).collect();
+        let separator = vec!["---"; self.columns.len()];
+
+        if self.groups.is_empty() {
+            writeln!(self.writer, "No results.")?;
+        }
+
+        for (class, rows) in &self.groups {
+            writeln!(self.writer, "## {}xx ({} results)", class, rows.len())?;
+            writeln!(self.writer)?;
+            writeln!(self.writer, "| {} |", header.join(" | "))?;
+            writeln!(self.writer, "| {} |", separator.join(" | "))?;
+            for row in rows {
+                writeln!(self.writer, "| {} |", row.join(" | "))?;
+            }
+            writeln!(self.writer)?;
+        }
+
+        self.writer.flush()
+    }
+}
+
+impl ResultWriter for MarkdownWriter {
+    fn write_header(&mut self, args: &DisplayArgs) -> Result<(), OutputError> {
+        self.write_intro(args).map_err(|_| OutputError::WriteFailed)
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        let row = self.columns.iter().map(|c| escape(&c.value(record))).collect();
+        self.groups.entry(record.status / 100).or_default().push(row);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        self.write_groups().map_err(|_| OutputError::WriteFailed)
+    }
+}
+
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
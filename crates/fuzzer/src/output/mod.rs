@@ -0,0 +1,323 @@
+pub mod csv;
+pub mod evidence;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod text;
+
+use crate::DisplayArgs;
+use crate::output::{
+    csv::CsvWriter, evidence::EvidenceWriter, html::HtmlWriter, json::JsonWriter, markdown::MarkdownWriter,
+    text::TextWriter,
+};
+use crate::order::ResultOrder;
+use crate::recursion::Job;
+use crate::response::FuzzResponse;
+use crate::stats::{ErrorKind, ScanSummary};
+
+use colored::*;
+use serde::Serialize;
+use std::{path::Path, sync::Arc};
+
+pub enum OutputError {
+    FileNotCreated,
+    WriteFailed,
+    DirectoryNotCreated,
+}
+
+impl OutputError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputError::FileNotCreated => "Output file cannot be created",
+            OutputError::WriteFailed => "Output file cannot be written",
+            OutputError::DirectoryNotCreated => "Output directory cannot be created",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Jsonl,
+    Csv,
+    Md,
+    Html,
+    Txt,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Md => "md",
+            OutputFormat::Html => "html",
+            OutputFormat::Txt => "txt",
+        }
+    }
+
+    /// Guesses the format from the file extension, `None` when the extension is unknown.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let format = match Path::new(path).extension().and_then(|e| e.to_str())? {
+            "json" => OutputFormat::Json,
+            "jsonl" | "ndjson" => OutputFormat::Jsonl,
+            "csv" => OutputFormat::Csv,
+            "md" | "markdown" => OutputFormat::Md,
+            "html" | "htm" => OutputFormat::Html,
+            "txt" => OutputFormat::Txt,
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct OutputTarget {
+    pub path: String,
+    pub format: OutputFormat,
+}
+
+/// Columns available to tabular formats (CSV and Markdown).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Url,
+    Target,
+    Payload,
+    Method,
+    Status,
+    Size,
+    Words,
+    Lines,
+    Duration,
+    Redirect,
+    ContentType,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 6] = [
+        Column::Url,
+        Column::Status,
+        Column::Size,
+        Column::Words,
+        Column::Lines,
+        Column::Duration,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Column> {
+        let column = match name {
+            "url" => Column::Url,
+            "target" => Column::Target,
+            "payload" => Column::Payload,
+            "method" => Column::Method,
+            "status" => Column::Status,
+            "size" => Column::Size,
+            "words" => Column::Words,
+            "lines" => Column::Lines,
+            "duration" => Column::Duration,
+            "redirect" => Column::Redirect,
+            "content_type" | "content-type" => Column::ContentType,
+            _ => return None,
+        };
+
+        Some(column)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Column::Url => "url",
+            Column::Target => "target",
+            Column::Payload => "payload",
+            Column::Method => "method",
+            Column::Status => "status",
+            Column::Size => "size",
+            Column::Words => "words",
+            Column::Lines => "lines",
+            Column::Duration => "duration",
+            Column::Redirect => "redirect",
+            Column::ContentType => "content_type",
+        }
+    }
+
+    pub fn value(&self, record: &ResultRecord) -> String {
+        match self {
+            Column::Url => record.url.to_string(),
+            Column::Target => record.target.to_string(),
+            Column::Payload => record.payload.to_string(),
+            Column::Method => record.method.to_string(),
+            Column::Status => record.status.to_string(),
+            Column::Size => record.size.to_string(),
+            Column::Words => record.words.to_string(),
+            Column::Lines => record.lines.to_string(),
+            Column::Duration => format!("{}ms", record.duration_ms),
+            Column::Redirect => record.redirect.unwrap_or_default().to_string(),
+            Column::ContentType => record.content_type.unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// One result as written to output files.
+#[derive(Serialize)]
+pub struct ResultRecord<'a> {
+    pub url: &'a str,
+    pub target: &'a str,
+    pub payload: &'a str,
+    pub method: &'a str,
+    pub status: u16,
+    pub size: u64,
+    pub words: usize,
+    pub lines: usize,
+    pub duration_ms: u64,
+    pub redirect: Option<&'a str>,
+    pub content_type: Option<&'a str>,
+    #[serde(skip)]
+    pub response: &'a FuzzResponse,
+}
+
+impl<'a> ResultRecord<'a> {
+    pub fn new(response: &'a FuzzResponse, method: &'a str) -> Self {
+        Self {
+            url: &response.url,
+            target: &response.target,
+            payload: &response.payload,
+            method,
+            status: response.status,
+            size: response.size,
+            words: response.words,
+            lines: response.lines,
+            duration_ms: response.time.as_millis() as u64,
+            redirect: response.redirect(),
+            content_type: response.content_type(),
+            response,
+        }
+    }
+}
+
+/// A file format results can be written in.
+pub trait ResultWriter: Send {
+    fn write_header(&mut self, args: &DisplayArgs) -> Result<(), OutputError>;
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError>;
+    fn finish(&mut self) -> Result<(), OutputError>;
+}
+
+/// Receives the events of a scan. The terminal and every output file are sinks,
+/// and embedders can register their own with `Fuzzer::add_sink`.
+pub trait OutputSink: Send {
+    /// Called once before the first request, with the effective configuration.
+    fn on_start(&mut self, _args: &DisplayArgs) {}
+    /// Called for every response that went through the filter stage.
+    /// The response is shared by every sink: keep the `Arc` rather than a copy.
+    fn on_result(&mut self, response: &Arc<FuzzResponse>);
+    /// Called when a result starts a recursive scan, queued after the current one.
+    fn on_job(&mut self, _job: &Job) {}
+    /// Called when links found in a result are queued, to be requested once each by `job`.
+    fn on_links(&mut self, _job: &Job, _paths: &[String]) {}
+    /// Called when a payload could not be requested after all retries.
+    fn on_error(&mut self, _url: &str, _kind: ErrorKind) {}
+    /// Called after every payload of `job`, whatever its outcome, with `count` 1.
+    /// A resumed scan first reports the payloads done before it stopped.
+    fn on_progress(&mut self, _job: &Job, _count: usize) {}
+    /// Called when the interactive console pauses the scan, and when it resumes it.
+    fn on_pause(&mut self) {}
+    fn on_resume(&mut self) {}
+    /// Called when matchers or filters were changed from the interactive console.
+    fn on_update(&mut self, _args: &DisplayArgs) {}
+    fn on_finish(&mut self, _summary: &ScanSummary) {}
+}
+
+/// Adapts a file `ResultWriter` to the `OutputSink` interface.
+/// The first write error is kept and reported once the scan finishes.
+/// When results are sorted or grouped, they are held back and written in order at the end.
+pub struct FileSink {
+    writer: Box<dyn ResultWriter>,
+    method: String,
+    order: ResultOrder,
+    pending: Vec<Arc<FuzzResponse>>,
+    error: Option<OutputError>,
+}
+
+impl FileSink {
+    pub fn new(writer: Box<dyn ResultWriter>) -> Self {
+        Self {
+            writer,
+            method: String::new(),
+            order: ResultOrder::default(),
+            pending: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn write(&mut self, response: &FuzzResponse) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = self.writer.write_record(&ResultRecord::new(response, &self.method));
+        self.keep_error(result);
+    }
+
+    fn keep_error(&mut self, result: Result<(), OutputError>) {
+        if let Err(e) = result {
+            self.error.get_or_insert(e);
+        }
+    }
+}
+
+impl OutputSink for FileSink {
+    fn on_start(&mut self, args: &DisplayArgs) {
+        self.method = args.method.clone();
+        self.order = args.order;
+        let result = self.writer.write_header(args);
+        self.keep_error(result);
+    }
+
+    fn on_result(&mut self, response: &Arc<FuzzResponse>) {
+        match self.order.is_active() {
+            true => self.pending.push(Arc::clone(response)),
+            false => self.write(response),
+        }
+    }
+
+    fn on_finish(&mut self, _summary: &ScanSummary) {
+        let pending = std::mem::take(&mut self.pending);
+        for group in self.order.arrange(pending) {
+            for response in &group.responses {
+                self.write(response);
+            }
+        }
+
+        let result = self.writer.finish();
+        self.keep_error(result);
+
+        if let Some(e) = &self.error {
+            eprintln!("{}", e.as_str().red());
+        }
+    }
+}
+
+/// Creates a sink for every output file and the evidence directory requested in `args`.
+pub fn open_file_sinks(args: &DisplayArgs) -> Result<Vec<Box<dyn OutputSink>>, OutputError> {
+    let mut sinks: Vec<Box<dyn OutputSink>> = Vec::new();
+
+    for target in &args.outputs {
+        let writer: Box<dyn ResultWriter> = match target.format {
+            OutputFormat::Json => Box::new(JsonWriter::create(&target.path, false)?),
+            OutputFormat::Jsonl => Box::new(JsonWriter::create(&target.path, true)?),
+            OutputFormat::Csv => Box::new(CsvWriter::create(&target.path, &args.columns)?),
+            OutputFormat::Md => Box::new(MarkdownWriter::create(&target.path, &args.columns)?),
+            OutputFormat::Html => Box::new(HtmlWriter::create(&target.path)?),
+            OutputFormat::Txt => Box::new(TextWriter::create(
+                &target.path,
+                args.template.clone().unwrap_or_default(),
+            )?),
+        };
+        sinks.push(Box::new(FileSink::new(writer)));
+    }
+
+    if let Some(directory) = &args.output_dir {
+        sinks.push(Box::new(FileSink::new(Box::new(EvidenceWriter::create(directory)?))));
+    }
+
+    Ok(sinks)
+}
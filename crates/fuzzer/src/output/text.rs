@@ -0,0 +1,40 @@
+use crate::DisplayArgs;
+use crate::output::{OutputError, ResultRecord, ResultWriter};
+use crate::template::Template;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Writes one line per result, laid out with the output template.
+pub struct TextWriter {
+    writer: BufWriter<File>,
+    template: Template,
+}
+
+impl TextWriter {
+    pub fn create(path: &str, template: Template) -> Result<Self, OutputError> {
+        let file = File::create(path).map_err(|_| OutputError::FileNotCreated)?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            template,
+        })
+    }
+}
+
+impl ResultWriter for TextWriter {
+    fn write_header(&mut self, _args: &DisplayArgs) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &ResultRecord) -> Result<(), OutputError> {
+        let line = self.template.render(record.response, record.method, false);
+        writeln!(self.writer, "{}", line).map_err(|_| OutputError::WriteFailed)
+    }
+
+    fn finish(&mut self) -> Result<(), OutputError> {
+        self.writer.flush().map_err(|_| OutputError::WriteFailed)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Longest a worker sleeps before checking again whether the scan stopped or the rate changed.
+const STEP: Duration = Duration::from_millis(50);
+
+/// Spaces the requests of every worker evenly to stay under a number of requests per second.
+/// Workers claim their slot with an atomic update, without waiting on each other for a lock.
+pub struct RateLimiter {
+    /// Requests per second, 0 for no limit.
+    rate: AtomicU32,
+    /// Earliest time the next request may be sent, in nanoseconds since `start`.
+    next: AtomicU64,
+    /// Number of rate changes, for sleeping workers to claim a slot at the new rate.
+    changes: AtomicU64,
+    start: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: AtomicU32::new(rate),
+            next: AtomicU64::new(0),
+            changes: AtomicU64::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    /// Changes the rate of a running scan, 0 removing the limit.
+    pub fn set_rate(&self, rate: u32) {
+        self.rate.store(rate, Ordering::Relaxed);
+        self.next.store(self.now(), Ordering::Relaxed);
+        self.changes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Blocks until the calling worker may send its next request.
+    /// Returns false when `stopping` tells the scan stopped meanwhile, the request not to be sent.
+    pub fn wait(&self, stopping: impl Fn() -> bool) -> bool {
+        'claim: loop {
+            let rate = self.rate.load(Ordering::Relaxed);
+            if rate == 0 {
+                return true;
+            }
+
+            let changes = self.changes.load(Ordering::Relaxed);
+            let interval = Duration::from_secs(1).as_nanos() as u64 / rate as u64;
+            let now = self.now();
+            let slot = self
+                .next
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| Some(next.max(now) + interval))
+                .unwrap()
+                .max(now);
+
+            loop {
+                let now = self.now();
+                if slot <= now {
+                    return true;
+                }
+                if stopping() {
+                    return false;
+                }
+                // The slot was claimed at the previous rate.
+                if self.changes.load(Ordering::Relaxed) != changes {
+                    continue 'claim;
+                }
+                thread::sleep(Duration::from_nanos(slot - now).min(STEP));
+            }
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn spaces_requests_at_the_rate() {
+        let limiter = RateLimiter::new(20);
+        let started = Instant::now();
+
+        for _ in 0..5 {
+            assert!(limiter.wait(|| false));
+        }
+        // The first request goes at once, the next ones every 50ms.
+        assert!(started.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn wakes_up_on_a_new_rate() {
+        let limiter = Arc::new(RateLimiter::new(1));
+        assert!(limiter.wait(|| false));
+
+        let waiting = Arc::clone(&limiter);
+        let started = Instant::now();
+        let worker = thread::spawn(move || waiting.wait(|| false));
+        thread::sleep(Duration::from_millis(100));
+        limiter.set_rate(0);
+
+        assert!(worker.join().unwrap());
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn gives_up_when_the_scan_stops() {
+        let limiter = RateLimiter::new(1);
+        assert!(limiter.wait(|| false));
+
+        let started = Instant::now();
+        assert!(!limiter.wait(|| started.elapsed() >= Duration::from_millis(100)));
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+}
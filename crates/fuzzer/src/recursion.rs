@@ -0,0 +1,164 @@
+use crate::response::FuzzResponse;
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Mutex};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecursionStrategy {
+    /// Recurse into results that look like directories: a redirect to the same path
+    /// with a trailing slash, or a URL already ending with a slash.
+    Default,
+    /// Recurse into every result with a triggering status.
+    Greedy,
+}
+
+impl RecursionStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecursionStrategy::Default => "default",
+            RecursionStrategy::Greedy => "greedy",
+        }
+    }
+}
+
+/// A scan of the wordlist against one URL template.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Job {
+    /// URL template given by the user this job descends from.
+    pub target: String,
+    pub template: String,
+    /// 0 for the targets given by the user, parent depth + 1 for recursive scans.
+    pub depth: usize,
+    /// Whether the job requests links found in responses rather than the wordlist.
+    /// Its template is the origin of the target, and its payloads are paths.
+    #[serde(default)]
+    pub discovered: bool,
+}
+
+impl Job {
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            template: target.to_string(),
+            depth: 0,
+            discovered: false,
+        }
+    }
+}
+
+/// Decides which responses start a recursive scan, and makes sure each is started once.
+/// Filters do not apply: a filtered out response can still start one.
+pub struct Recursion {
+    max_depth: usize,
+    statuses: Vec<u16>,
+    strategy: RecursionStrategy,
+    queued: Mutex<HashSet<String>>,
+}
+
+impl Recursion {
+    pub fn new(max_depth: usize, statuses: Vec<u16>, strategy: RecursionStrategy, targets: &[String]) -> Self {
+        Self {
+            max_depth,
+            statuses,
+            strategy,
+            queued: Mutex::new(targets.iter().cloned().collect()),
+        }
+    }
+
+    /// Returns the job scanning the directory behind `response`, found by `job`,
+    /// unless it does not qualify, is too deep or was already queued.
+    pub fn next_job(&self, job: &Job, response: &FuzzResponse) -> Option<Job> {
+        if job.depth >= self.max_depth || !self.statuses.contains(&response.status) {
+            return None;
+        }
+
+        if self.strategy == RecursionStrategy::Default && !Self::looks_like_directory(response) {
+            return None;
+        }
+
+        let directory = response.payload.trim_end_matches('/');
+        if directory.is_empty() {
+            return None;
+        }
+
+        let template = job.template.replace("{}", &format!("{}/{{}}", directory));
+
+        match self.queued.lock().unwrap().insert(template.clone()) {
+            true => Some(Job {
+                target: job.target.clone(),
+                template,
+                depth: job.depth + 1,
+                discovered: false,
+            }),
+            false => None,
+        }
+    }
+
+    /// Templates of every job queued so far, targets included.
+    pub fn queued(&self) -> Vec<String> {
+        self.queued.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Marks the jobs of a resumed scan as queued, so they are not started twice.
+    pub fn restore(&self, templates: Vec<String>) {
+        self.queued.lock().unwrap().extend(templates);
+    }
+
+    fn looks_like_directory(response: &FuzzResponse) -> bool {
+        if response.url.ends_with('/') {
+            return true;
+        }
+
+        let directory = format!("{}/", response.url);
+        // Relative redirects only carry the path of the directory.
+        let path = directory
+            .split_once("://")
+            .and_then(|(_, rest)| rest.find('/').map(|i| &rest[i..]));
+
+        match response.redirect() {
+            Some(location) if location.starts_with('/') => Some(location) == path,
+            Some(location) => location == directory,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(url: &str, location: &str) -> FuzzResponse {
+        let mut response = FuzzResponse::fixture(301, url, "admin", "");
+        response.headers.insert("location", location.parse().unwrap());
+        response
+    }
+
+    #[test]
+    fn recognizes_directories() {
+        assert!(Recursion::looks_like_directory(&FuzzResponse::fixture(200, "http://target/admin/", "admin/", "")));
+        assert!(Recursion::looks_like_directory(&redirect("http://target/admin", "http://target/admin/")));
+        assert!(Recursion::looks_like_directory(&redirect("http://target/app/admin", "/app/admin/")));
+    }
+
+    #[test]
+    fn ignores_other_pages_and_redirects() {
+        assert!(!Recursion::looks_like_directory(&FuzzResponse::fixture(200, "http://target/admin", "admin", "")));
+        assert!(!Recursion::looks_like_directory(&redirect("http://target/admin", "/login")));
+        assert!(!Recursion::looks_like_directory(&redirect("http://target/admin", "/admin")));
+        assert!(!Recursion::looks_like_directory(&redirect("http://target/admin", "https://target/admin/")));
+    }
+
+    #[test]
+    fn queues_each_directory_once_up_to_the_depth() {
+        let recursion = Recursion::new(1, vec![301], RecursionStrategy::Default, &[String::from("http://target/{}")]);
+        let job = Job::new("http://target/{}");
+        let response = redirect("http://target/admin", "/admin/");
+
+        let next = recursion.next_job(&job, &response).unwrap();
+        assert_eq!(next.template, "http://target/admin/{}");
+        assert_eq!(next.depth, 1);
+        assert!(recursion.next_job(&job, &response).is_none());
+        assert!(recursion.next_job(&next, &redirect("http://target/admin/old", "/admin/old/")).is_none());
+    }
+}
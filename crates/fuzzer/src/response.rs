@@ -0,0 +1,205 @@
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::{Version, header::HeaderMap};
+use sender::sender::Exchange;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A response of the target. It is serialized to keep results in checkpoints.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FuzzResponse {
+    pub url: String,
+    /// URL template of the target the request was made for.
+    pub target: String,
+    pub payload: String,
+    /// Whether the URL was found in another response rather than built from the wordlist.
+    #[serde(default)]
+    pub discovered: bool,
+    /// Headers the request was sent with, `Host` aside.
+    #[serde(default, with = "header_map")]
+    pub request_headers: HeaderMap,
+    pub status: u16,
+    #[serde(with = "http_version")]
+    pub version: Version,
+    pub size: u64,
+    pub words: usize,
+    pub lines: usize,
+    pub time: Duration,
+    #[serde(with = "header_map")]
+    pub headers: HeaderMap,
+    /// Body decoded with the charset of the response, UTF-8 by default.
+    pub body: String,
+    /// Body as received, when decoding it changed its bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<u8>>,
+    /// Whether the body was dropped, the response being only kept as a record of the result.
+    #[serde(default)]
+    pub stripped: bool,
+}
+
+impl FuzzResponse {
+    pub fn new(exchange: Exchange, url: &str, payload: &str, target: &str) -> Self {
+        let Exchange {
+            request_headers,
+            response,
+            time,
+        } = exchange;
+        let status = response.status().as_u16();
+        let version = response.version();
+        let content_length = response.content_length();
+        let headers = response.headers().clone();
+        let bytes = response.bytes().unwrap_or_default();
+
+        let body = Self::decode(&headers, &bytes);
+        let raw = (body.as_bytes() != &bytes[..]).then(|| bytes.to_vec());
+
+        Self {
+            url: url.trim().to_string(),
+            target: target.to_string(),
+            payload: payload.trim().to_string(),
+            discovered: false,
+            request_headers,
+            status,
+            version,
+            size: content_length.unwrap_or(bytes.len() as u64),
+            words: body.split_whitespace().count(),
+            lines: body.lines().count(),
+            time,
+            headers,
+            body,
+            raw,
+            stripped: false,
+        }
+    }
+
+    /// Decodes `bytes` with the charset of the `Content-Type` header, as `Response::text` does.
+    fn decode(headers: &HeaderMap, bytes: &[u8]) -> String {
+        let charset = headers
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|content_type| {
+                content_type.split(';').skip(1).find_map(|parameter| {
+                    let (name, value) = parameter.split_once('=')?;
+                    name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"'))
+                })
+            });
+        let encoding = charset
+            .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(UTF_8);
+
+        encoding.decode(bytes).0.into_owned()
+    }
+
+    /// Copy of the response without its body, light enough to keep for every result.
+    pub fn without_body(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            target: self.target.clone(),
+            payload: self.payload.clone(),
+            request_headers: self.request_headers.clone(),
+            headers: self.headers.clone(),
+            body: String::new(),
+            raw: None,
+            stripped: true,
+            ..*self
+        }
+    }
+
+    /// Body bytes exactly as the target sent them.
+    pub fn raw_body(&self) -> &[u8] {
+        self.raw.as_deref().unwrap_or(self.body.as_bytes())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    pub fn redirect(&self) -> Option<&str> {
+        self.header("location")
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    /// Text of the HTML `<title>` element, whitespace collapsed.
+    pub fn title(&self) -> Option<String> {
+        let lowercase = self.body.to_ascii_lowercase();
+        let open = lowercase.find("<title")?;
+        let start = open + lowercase[open..].find('>')? + 1;
+        let end = start + lowercase[start..].find("</title")?;
+
+        let title = self.body[start..end].split_whitespace().collect::<Vec<_>>().join(" ");
+        (!title.is_empty()).then_some(title)
+    }
+}
+
+mod http_version {
+    use reqwest::Version;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(version: &Version, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:?}", version))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Version, D::Error> {
+        let version = match String::deserialize(deserializer)?.as_str() {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            _ => Version::HTTP_11,
+        };
+        Ok(version)
+    }
+}
+
+mod header_map {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(headers: &HeaderMap, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), String::from_utf8_lossy(value.as_bytes()))),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+        let pairs: Vec<(String, String)> = Vec::deserialize(deserializer)?;
+
+        Ok(pairs
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(&value).ok()?,
+                ))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+impl FuzzResponse {
+    /// Response with the given status and body, as if `payload` was requested on `url`.
+    pub fn fixture(status: u16, url: &str, payload: &str, body: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            target: url.replace(payload, "{}"),
+            payload: payload.to_string(),
+            discovered: false,
+            request_headers: HeaderMap::new(),
+            status,
+            version: Version::HTTP_11,
+            size: body.len() as u64,
+            words: body.split_whitespace().count(),
+            lines: body.lines().count(),
+            time: Duration::from_millis(120),
+            headers: HeaderMap::new(),
+            body: body.to_string(),
+            raw: None,
+            stripped: false,
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::recursion::Job;
+
+use reader::Reader;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Payloads per chunk of the jobs that do not read a wordlist.
+const BACKLOG_CHUNK_SIZE: usize = 50;
+
+struct Task {
+    job: Job,
+    /// `None` for jobs of discovered links, which only request their backlog.
+    reader: Option<Reader>,
+    /// Payloads to hand out before reading on, left over from a resumed scan or discovered.
+    backlog: VecDeque<String>,
+}
+
+struct Host {
+    name: String,
+    tasks: VecDeque<Task>,
+}
+
+/// A chunk handed out to a worker and not fully processed yet.
+struct InFlight {
+    job: Job,
+    words: Vec<String>,
+    /// Shared with the worker, which counts the payloads it requested without locking the scheduler.
+    done: Arc<AtomicUsize>,
+    /// Reader position once the chunk was read.
+    position: u64,
+    lines_read: usize,
+}
+
+#[derive(Default)]
+struct State {
+    hosts: VecDeque<Host>,
+    /// In-flight chunks can still queue recursive jobs,
+    /// so workers wait for them before giving up on an empty queue.
+    in_flight: HashMap<u64, InFlight>,
+    next_id: u64,
+    stopped: bool,
+    /// When each job got its first chunk. Jobs of discovered links share the template
+    /// of their origin, so the whole job is the key.
+    started: HashMap<Job, Instant>,
+    /// Jobs abandoned for running out of time.
+    expired: Vec<Job>,
+}
+
+/// Payloads to request for a job, handed out by the scheduler.
+pub struct Chunk {
+    pub id: u64,
+    pub job: Job,
+    pub words: Vec<String>,
+    done: Arc<AtomicUsize>,
+    /// When the job runs out of time, if it has a time limit.
+    deadline: Option<Instant>,
+}
+
+/// Where a job stands: everything before `position` in the wordlist
+/// was requested, except the `pending` payloads.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JobState {
+    pub job: Job,
+    pub position: u64,
+    pub lines_read: usize,
+    pub pending: Vec<String>,
+}
+
+impl JobState {
+    /// Number of payloads of the job already requested.
+    pub fn completed(&self) -> usize {
+        self.lines_read.saturating_sub(self.pending.len())
+    }
+}
+
+/// Hands out wordlist chunks of every running job to the worker threads.
+///
+/// Hosts take turns, and so do the jobs of a host, so that one large or deeply
+/// recursed target does not starve the others of the shared thread budget.
+pub struct Scheduler {
+    state: Mutex<State>,
+    changed: Condvar,
+    job_time_limit: Option<Duration>,
+    /// Payloads every wordlist job requests before its wordlist.
+    extra_payloads: Vec<String>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+            job_time_limit: None,
+            extra_payloads: Vec::new(),
+        }
+    }
+
+    /// Adds `payloads` to the wordlist of every job pushed afterwards.
+    pub fn with_extra_payloads(mut self, payloads: Vec<String>) -> Self {
+        self.extra_payloads = payloads;
+        self
+    }
+
+    /// Abandons each job once it ran for `limit`, its remaining payloads being skipped.
+    pub fn with_job_time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.job_time_limit = limit;
+        self
+    }
+
+    pub fn push(&self, job: Job, reader: Reader) {
+        self.push_task(Task {
+            job,
+            reader: Some(reader),
+            backlog: self.extra_payloads.iter().cloned().collect(),
+        });
+    }
+
+    /// Queues payloads to request once each, outside of any wordlist.
+    /// They are added to `job` if it is still waiting for its turn.
+    pub fn push_payloads(&self, job: Job, payloads: Vec<String>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let queued = state
+                .hosts
+                .iter_mut()
+                .flat_map(|host| host.tasks.iter_mut())
+                .find(|task| task.job == job);
+
+            if let Some(task) = queued {
+                task.backlog.extend(payloads);
+                self.changed.notify_all();
+                return;
+            }
+        }
+
+        self.push_task(Task {
+            job,
+            reader: None,
+            backlog: payloads.into(),
+        });
+    }
+
+    /// Queues a job of a resumed scan, `reader` being already at the saved position.
+    pub fn restore(&self, state: JobState, reader: Option<Reader>) {
+        self.push_task(Task {
+            job: state.job,
+            reader,
+            backlog: state.pending.into(),
+        });
+    }
+
+    fn push_task(&self, task: Task) {
+        let mut state = self.state.lock().unwrap();
+        let name = host(&task.job.template).to_string();
+
+        match state.hosts.iter_mut().find(|host| host.name == name) {
+            Some(host) => host.tasks.push_back(task),
+            None => state.hosts.push_back(Host {
+                name,
+                tasks: VecDeque::from([task]),
+            }),
+        }
+
+        self.changed.notify_all();
+    }
+
+    /// Returns the next chunk to request, or `None` once every job is exhausted or the scan stopped.
+    /// Workers report each payload with `advance` and the whole chunk with `done`.
+    pub fn next_chunk(&self) -> Option<Chunk> {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if state.stopped {
+                return None;
+            }
+
+            let Some(mut host) = state.hosts.pop_front() else {
+                if state.in_flight.is_empty() {
+                    return None;
+                }
+                state = self.changed.wait(state).unwrap();
+                continue;
+            };
+
+            let Some(mut task) = host.tasks.pop_front() else {
+                continue;
+            };
+
+            if Self::expire(&mut state, self.job_time_limit, &task.job) {
+                if !host.tasks.is_empty() {
+                    state.hosts.push_back(host);
+                }
+                continue;
+            }
+
+            // The backlog is handed out in chunks too, so that every thread can take a share of it.
+            let words = match (task.backlog.is_empty(), &mut task.reader) {
+                (false, reader) => {
+                    let size = reader.as_ref().map_or(BACKLOG_CHUNK_SIZE, |reader| reader.chunk_size);
+                    let size = size.min(task.backlog.len());
+                    Some(task.backlog.drain(..size).collect())
+                }
+                (true, Some(reader)) => reader.get_next_chunk().ok(),
+                (true, None) => None,
+            };
+            let job = task.job.clone();
+            let (position, lines_read) = Self::position(&task);
+
+            if words.is_some() {
+                host.tasks.push_back(task);
+            }
+            if !host.tasks.is_empty() {
+                state.hosts.push_back(host);
+            }
+
+            if let Some(words) = words {
+                let started = *state.started.entry(job.clone()).or_insert_with(Instant::now);
+                let deadline = self.job_time_limit.map(|limit| started + limit);
+                let id = state.next_id;
+                let done = Arc::new(AtomicUsize::new(0));
+                state.next_id += 1;
+                state.in_flight.insert(
+                    id,
+                    InFlight {
+                        job: job.clone(),
+                        words: words.clone(),
+                        done: Arc::clone(&done),
+                        position,
+                        lines_read,
+                    },
+                );
+                return Some(Chunk {
+                    id,
+                    job,
+                    words,
+                    done,
+                    deadline,
+                });
+            }
+        }
+    }
+
+    /// Whether the job of `chunk` ran out of time, in which case its remaining payloads are skipped.
+    /// The scheduler is only locked once the deadline passed, to record the job as expired.
+    pub fn expired(&self, chunk: &Chunk) -> bool {
+        if chunk.deadline.is_none_or(|deadline| Instant::now() < deadline) {
+            return false;
+        }
+        Self::expire(&mut self.state.lock().unwrap(), self.job_time_limit, &chunk.job)
+    }
+
+    fn expire(state: &mut State, limit: Option<Duration>, job: &Job) -> bool {
+        let expired = limit.is_some_and(|limit| {
+            state.started.get(job).is_some_and(|started| started.elapsed() >= limit)
+        });
+        if expired && !state.expired.contains(job) {
+            state.expired.push(job.clone());
+        }
+        expired
+    }
+
+    /// Templates of the jobs abandoned for running out of time, each listed once.
+    pub fn expired_jobs(&self) -> Vec<String> {
+        let mut templates: Vec<String> = Vec::new();
+        for job in &self.state.lock().unwrap().expired {
+            if !templates.contains(&job.template) {
+                templates.push(job.template.clone());
+            }
+        }
+        templates
+    }
+
+    /// Records that one more payload of the chunk was requested.
+    pub fn advance(&self, chunk: &Chunk) {
+        chunk.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn done(&self, id: u64) {
+        self.state.lock().unwrap().in_flight.remove(&id);
+        self.changed.notify_all();
+    }
+
+    /// Stops handing out chunks. Chunks left unfinished stay pending in snapshots.
+    pub fn stop(&self) {
+        self.state.lock().unwrap().stopped = true;
+        self.changed.notify_all();
+    }
+
+    /// State of every job not finished yet, to save a checkpoint.
+    pub fn snapshot(&self) -> Vec<JobState> {
+        let state = self.state.lock().unwrap();
+        let mut jobs: Vec<JobState> = Vec::new();
+
+        for task in state.hosts.iter().flat_map(|host| &host.tasks) {
+            let (position, lines_read) = Self::position(task);
+            jobs.push(JobState {
+                job: task.job.clone(),
+                position,
+                lines_read,
+                pending: task.backlog.iter().cloned().collect(),
+            });
+        }
+
+        for chunk in state.in_flight.values() {
+            let pending = &chunk.words[chunk.done.load(Ordering::Relaxed)..];
+
+            match jobs.iter_mut().find(|job| job.job == chunk.job) {
+                Some(job) => {
+                    job.pending.extend_from_slice(pending);
+                    // The wordlist of a job is read further than any of its chunks.
+                    if chunk.position > job.position {
+                        job.position = chunk.position;
+                        job.lines_read = chunk.lines_read;
+                    }
+                }
+                None => jobs.push(JobState {
+                    job: chunk.job.clone(),
+                    position: chunk.position,
+                    lines_read: chunk.lines_read,
+                    pending: pending.to_vec(),
+                }),
+            }
+        }
+
+        jobs
+    }
+
+    fn position(task: &Task) -> (u64, usize) {
+        task.reader.as_ref().map_or((0, 0), |reader| (reader.position, reader.lines_read))
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Host part of a URL template, used to share the threads fairly between hosts.
+fn host(template: &str) -> &str {
+    let rest = template.split_once("://").map_or(template, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+
+    // Jobs of discovered links have the origin of their target as template, followed by the placeholder.
+    match host.strip_suffix("{}") {
+        Some(origin) if host.len() == rest.len() && !origin.is_empty() => origin,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_jobs_by_host() {
+        assert_eq!(host("http://target:8080/{}"), "target:8080");
+        assert_eq!(host("https://target/admin/{}?q=1"), "target");
+        assert_eq!(host("https://target{}"), "target");
+        assert_eq!(host("http://{}.target/"), "{}.target");
+        assert_eq!(host("http://api-{}.target"), "api-{}.target");
+        assert_eq!(host("target/{}"), "target");
+    }
+
+    #[test]
+    fn times_out_jobs_sharing_a_template_apart() {
+        let scheduler = Scheduler::new().with_job_time_limit(Some(Duration::from_millis(100)));
+        let links = |depth| Job {
+            target: String::from("http://target/{}"),
+            template: String::from("http://target{}"),
+            depth,
+            discovered: true,
+        };
+
+        scheduler.push_payloads(links(1), vec![String::from("/a")]);
+        let first = scheduler.next_chunk().unwrap();
+        scheduler.done(first.id);
+        std::thread::sleep(Duration::from_millis(150));
+
+        // Links found one level deeper get a time budget of their own.
+        scheduler.push_payloads(links(2), vec![String::from("/a/b")]);
+        let second = scheduler.next_chunk().unwrap();
+        assert_eq!(second.job.depth, 2);
+        assert!(!scheduler.expired(&second));
+        assert!(scheduler.expired(&first));
+        assert_eq!(scheduler.expired_jobs(), vec![String::from("http://target{}")]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::rate::RateLimiter;
+use crate::recursion::Job;
+
+use regex::Regex;
+use reqwest::Url;
+use sender::{Sender, is_host_template};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+/// Sitemaps fetched at most per target, sitemap indexes included.
+const MAX_SITEMAPS: usize = 20;
+
+/// Paths a target discloses in its `robots.txt` and sitemaps.
+#[derive(Clone, Debug, Serialize)]
+pub struct Seeds {
+    pub target: String,
+    /// Origin of the target, the paths being relative to it.
+    pub origin: String,
+    /// Paths and queries, each requested once.
+    pub paths: Vec<String>,
+}
+
+impl Seeds {
+    /// Reads `/robots.txt` and `/sitemap.xml` of the target, and the sitemaps they point to.
+    /// Files that cannot be fetched are skipped: most targets have neither. Targets with the
+    /// payload in the host name have no single origin to read them from, and are not seeded.
+    pub fn fetch(sender: &Sender, rate: &RateLimiter, target: &str) -> Self {
+        let mut seeds = Self {
+            target: target.to_string(),
+            origin: String::new(),
+            paths: Vec::new(),
+        };
+        if is_host_template(target) {
+            return seeds;
+        }
+        let Ok(base) = Url::parse(&target.replace("{}", "")) else {
+            return seeds;
+        };
+        seeds.origin = base.origin().ascii_serialization();
+
+        let mut sitemaps = vec![format!("{}/sitemap.xml", seeds.origin)];
+        let mut known = HashSet::new();
+
+        if let Some(robots) = Self::get(sender, rate, &format!("{}/robots.txt", seeds.origin)) {
+            let (paths, links) = parse_robots(&robots);
+            for path in paths {
+                seeds.add(&mut known, &base, path);
+            }
+            sitemaps.extend(links.into_iter().filter_map(|link| seeds.resolve(&base, link)).map(String::from));
+        }
+
+        let mut fetched = HashSet::new();
+
+        while let Some(sitemap) = sitemaps.pop() {
+            if fetched.len() == MAX_SITEMAPS || !fetched.insert(sitemap.clone()) {
+                continue;
+            }
+            let Some(body) = Self::get(sender, rate, &sitemap) else { continue };
+
+            let index = body.contains("<sitemapindex");
+            for location in sitemap_locations(&body) {
+                match index {
+                    true => sitemaps.extend(seeds.resolve(&base, &location).map(String::from)),
+                    false => seeds.add(&mut known, &base, &location),
+                }
+            }
+        }
+
+        seeds
+    }
+
+    /// Body of `url`, requested like the payloads: with the headers of the scan, within its rate.
+    fn get(sender: &Sender, rate: &RateLimiter, url: &str) -> Option<String> {
+        rate.wait(|| false);
+        let response = sender.get(url).ok()?.response;
+        if !response.status().is_success() {
+            return None;
+        }
+        response.text().ok()
+    }
+
+    /// URL of `link` if it is on the origin of the target, which is the only one fetched or requested.
+    fn resolve(&self, base: &Url, link: &str) -> Option<Url> {
+        let url = base.join(link).ok()?;
+        (url.origin().ascii_serialization() == self.origin).then_some(url)
+    }
+
+    /// Keeps `link` if it is on the origin of the target and not `known` yet.
+    fn add(&mut self, known: &mut HashSet<String>, base: &Url, link: &str) {
+        let Some(url) = self.resolve(base, link) else { return };
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        if known.insert(path.clone()) {
+            self.paths.push(path);
+        }
+    }
+
+    /// Job requesting the disclosed paths, once each.
+    pub fn job(&self) -> Job {
+        Job {
+            target: self.target.clone(),
+            template: format!("{}{{}}", self.origin),
+            depth: 0,
+            discovered: true,
+        }
+    }
+
+    /// Last segment of every disclosed path, to request as payloads of every wordlist job.
+    /// Words already in the wordlist are left out.
+    pub fn words(seeds: &[Seeds], wordlist: &str) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        let mut unique = HashSet::new();
+        for path in seeds.iter().flat_map(|seeds| &seeds.paths) {
+            let path = path.split('?').next().unwrap_or_default().trim_end_matches('/');
+            let word = path.rsplit('/').next().unwrap_or_default();
+            if !word.is_empty() && unique.insert(word) {
+                words.push(word.to_string());
+            }
+        }
+
+        if let Ok(file) = File::open(wordlist) {
+            let known: HashSet<String> = BufReader::new(file)
+                .lines()
+                .map_while(Result::ok)
+                .filter(|line| unique.contains(line.trim_end()))
+                .map(|line| line.trim_end().to_string())
+                .collect();
+            words.retain(|word| !known.contains(word));
+        }
+
+        words
+    }
+}
+
+/// Paths of the `Allow` and `Disallow` rules of a `robots.txt`, and the links of its `Sitemap` lines.
+/// Patterns are cut at their first wildcard, the rest being unknown.
+fn parse_robots(robots: &str) -> (Vec<&str>, Vec<&str>) {
+    let mut paths = Vec::new();
+    let mut sitemaps = Vec::new();
+
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "allow" | "disallow" => {
+                let path = value.split(['*', '$']).next().unwrap_or_default();
+                if path.starts_with('/') && path != "/" {
+                    paths.push(path);
+                }
+            }
+            "sitemap" if !value.is_empty() => sitemaps.push(value),
+            _ => {}
+        }
+    }
+
+    (paths, sitemaps)
+}
+
+/// Content of the `<loc>` elements of a sitemap or sitemap index.
+fn sitemap_locations(body: &str) -> Vec<String> {
+    let locations = Regex::new(r"(?is)<loc>\s*(.*?)\s*</loc>").unwrap();
+    locations
+        .captures_iter(body)
+        .filter_map(|captures| captures.get(1))
+        .map(|location| location.as_str().replace("&amp;", "&"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_rules_and_sitemaps_of_robots() {
+        let robots = "\
+User-agent: *
+Disallow: /admin/
+disallow: /private/*.bak   # backups
+Allow: /public$
+Disallow: /
+Disallow:
+Disallow: *.php
+Sitemap: https://target/sitemap-pages.xml
+SITEMAP: /news.xml
+";
+        let (paths, sitemaps) = parse_robots(robots);
+
+        assert_eq!(paths, vec!["/admin/", "/private/", "/public"]);
+        assert_eq!(sitemaps, vec!["https://target/sitemap-pages.xml", "/news.xml"]);
+    }
+
+    #[test]
+    fn reads_sitemap_locations() {
+        let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://target/about</loc></url>
+  <url>
+    <LOC>
+      https://target/search?q=a&amp;page=2
+    </LOC>
+  </url>
+</urlset>"#;
+
+        assert_eq!(
+            sitemap_locations(sitemap),
+            vec!["https://target/about", "https://target/search?q=a&page=2"]
+        );
+    }
+
+    #[test]
+    fn keeps_links_of_the_target_origin() {
+        let base = Url::parse("https://target/app/").unwrap();
+        let mut seeds = Seeds {
+            target: String::from("https://target/app/{}"),
+            origin: String::from("https://target"),
+            paths: Vec::new(),
+        };
+        let mut known = HashSet::new();
+
+        for link in ["https://target/about", "/about", "https://other/about", "http://target/login", "docs?v=2"] {
+            seeds.add(&mut known, &base, link);
+        }
+        assert_eq!(seeds.paths, vec!["/about", "/app/docs?v=2"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+/// Token multiset of a response body, used to compare pages that differ
+/// only by a few bytes (timestamps, reflected paths, CSRF tokens...).
+pub struct Fingerprint {
+    tokens: HashMap<String, usize>,
+    total: usize,
+}
+
+impl Fingerprint {
+    /// Builds the fingerprint of `body` once the reflections of `payload` are removed,
+    /// so that pages reflecting the requested path compare equal to each other.
+    pub fn new(body: &str, payload: &str) -> Self {
+        let body = strip_reflections(&body.to_lowercase(), &payload.to_lowercase());
+
+        let mut tokens = HashMap::new();
+        let mut total = 0;
+
+        for token in body.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()) {
+            *tokens.entry(token.to_string()).or_insert(0) += 1;
+            total += 1;
+        }
+
+        Self { tokens, total }
+    }
+
+    /// Dice coefficient of the two token multisets, between 0 and 1.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        if self.total == 0 && other.total == 0 {
+            return 1.0;
+        }
+
+        let common: usize = self
+            .tokens
+            .iter()
+            .map(|(token, count)| (*count).min(other.tokens.get(token).copied().unwrap_or(0)))
+            .sum();
+
+        (2 * common) as f64 / (self.total + other.total) as f64
+    }
+}
+
+/// Replaces with a space each occurrence of `payload` that stands as a whole token or path
+/// segment of `text`, i.e. is not glued to a letter or digit. Shorter payloads such as `js`
+/// would otherwise cut words like `json` apart.
+pub fn strip_reflections(text: &str, payload: &str) -> String {
+    if payload.is_empty() {
+        return text.to_string();
+    }
+
+    let mut stripped = String::with_capacity(text.len());
+    let mut copied = 0;
+
+    for (start, _) in text.match_indices(payload) {
+        let end = start + payload.len();
+        let glued_before = text[..start].chars().next_back().is_some_and(char::is_alphanumeric);
+        let glued_after = text[end..].chars().next().is_some_and(char::is_alphanumeric);
+        if start < copied || glued_before || glued_after {
+            continue;
+        }
+
+        stripped.push_str(&text[copied..start]);
+        stripped.push(' ');
+        copied = end;
+    }
+
+    stripped.push_str(&text[copied..]);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_whole_tokens_and_segments() {
+        assert_eq!(strip_reflections("/admin/ not found", "admin"), "/ / not found");
+        assert_eq!(strip_reflections("admin", "admin"), " ");
+        assert_eq!(strip_reflections("app.js and js", "js"), "app.  and  ");
+        assert_eq!(strip_reflections("<b>a/b</b>", "a/b"), "<b> </b>");
+    }
+
+    #[test]
+    fn keeps_payloads_inside_words() {
+        assert_eq!(strip_reflections("a json api at apis", "api"), "a json   at apis");
+        assert_eq!(strip_reflections("banana", "a"), "banana");
+        assert_eq!(strip_reflections("text", ""), "text");
+    }
+
+    #[test]
+    fn ignores_reflections_of_short_payloads() {
+        let baseline = Fingerprint::new("<p>The page /xq7kfz3 was not found on this server</p>", "xq7kfz3");
+        let soft_404 = Fingerprint::new("<p>The page /a was not found on this server</p>", "a");
+
+        assert_eq!(baseline.similarity(&soft_404), 1.0);
+    }
+
+    #[test]
+    fn compares_token_multisets() {
+        let page = Fingerprint::new("one two three four", "");
+
+        assert_eq!(page.similarity(&Fingerprint::new("four three two one", "")), 1.0);
+        assert_eq!(page.similarity(&Fingerprint::new("one two", "")), 2.0 * 2.0 / 6.0);
+        assert_eq!(page.similarity(&Fingerprint::new("", "")), 0.0);
+        assert_eq!(Fingerprint::new("", "").similarity(&Fingerprint::new("", "")), 1.0);
+    }
+}
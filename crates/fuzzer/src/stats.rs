@@ -0,0 +1,303 @@
+use crate::filter::dedupe::DuplicateGroup;
+use crate::limits::StopReason;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// What became of a response once it went through the filters.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Matched,
+    Filtered,
+    Duplicate,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    Timeout,
+    Dns,
+    Tls,
+    Connect,
+    Other,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Dns => "dns",
+            ErrorKind::Tls => "tls",
+            ErrorKind::Connect => "connect",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+impl From<&reqwest::Error> for ErrorKind {
+    fn from(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return ErrorKind::Timeout;
+        }
+
+        // DNS and TLS failures surface as connect errors; tell them apart from their causes.
+        let mut causes = String::new();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            causes.push_str(&cause.to_string().to_lowercase());
+            causes.push('\n');
+            source = cause.source();
+        }
+
+        if causes.contains("dns error") || causes.contains("failed to lookup address") {
+            ErrorKind::Dns
+        } else if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|needle| causes.contains(needle))
+        {
+            ErrorKind::Tls
+        } else if error.is_connect() {
+            ErrorKind::Connect
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+/// Figures of a finished scan.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanSummary {
+    pub total_requests: usize,
+    pub matched: usize,
+    pub filtered: usize,
+    pub duplicates: usize,
+    pub errored: usize,
+    pub status_codes: BTreeMap<u16, usize>,
+    pub errors: BTreeMap<ErrorKind, usize>,
+    pub average_latency: Duration,
+    pub p95_latency: Duration,
+    pub requests_per_second: f64,
+    pub elapsed: Duration,
+    /// Groups of collapsed responses, empty unless deduplication is enabled.
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    /// Why the scan ended before every payload was requested.
+    pub stopped: Option<StopReason>,
+    /// Checkpoint file the scan can be resumed from, when it was stopped.
+    pub checkpoint: Option<String>,
+    /// Templates of the jobs abandoned for running out of time.
+    pub expired_jobs: Vec<String>,
+}
+
+/// Running totals of a scan, saved in checkpoints.
+/// Latencies are not saved: a resumed scan reports those of its own requests.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Counters {
+    responses: usize,
+    matched: usize,
+    filtered: usize,
+    duplicates: usize,
+    status_codes: BTreeMap<u16, usize>,
+    errors: BTreeMap<ErrorKind, usize>,
+    /// Time spent scanning before the scan was resumed.
+    elapsed: Duration,
+    #[serde(skip)]
+    latencies: Latencies,
+}
+
+/// Latencies below this many microseconds have a bucket each.
+const EXACT_LATENCIES: u64 = 64;
+/// Buckets per power of two above `EXACT_LATENCIES`, bounding the error of quantiles to about 3%.
+const SUB_BUCKETS: u64 = 32;
+
+/// Histogram of response times in microseconds, of fixed size whatever the number of requests.
+#[derive(Clone, Default)]
+struct Latencies {
+    buckets: Vec<u64>,
+    count: u64,
+    total: Duration,
+}
+
+impl Latencies {
+    fn record(&mut self, time: Duration) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; Self::bucket(u64::MAX) + 1];
+        }
+        let micros = u64::try_from(time.as_micros()).unwrap_or(u64::MAX);
+        self.buckets[Self::bucket(micros)] += 1;
+        self.count += 1;
+        self.total += time;
+    }
+
+    fn bucket(micros: u64) -> usize {
+        if micros < EXACT_LATENCIES {
+            return micros as usize;
+        }
+        // The 5 bits after the leading one pick the sub-bucket.
+        let exponent = 63 - micros.leading_zeros() as u64;
+        let mantissa = micros >> (exponent - 5);
+        (EXACT_LATENCIES + (exponent - 6) * SUB_BUCKETS + mantissa - SUB_BUCKETS) as usize
+    }
+
+    /// Middle of the range of latencies the bucket holds.
+    fn value(bucket: usize) -> Duration {
+        let bucket = bucket as u64;
+        if bucket < EXACT_LATENCIES {
+            return Duration::from_micros(bucket);
+        }
+        let exponent = (bucket - EXACT_LATENCIES) / SUB_BUCKETS + 6;
+        let mantissa = (bucket - EXACT_LATENCIES) % SUB_BUCKETS + SUB_BUCKETS;
+        let width = 1 << (exponent - 5);
+        Duration::from_micros((mantissa << (exponent - 5)) + (width - 1) / 2)
+    }
+
+    fn average(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total.div_f64(count as f64),
+        }
+    }
+
+    /// Latency that `percent` of the responses did not exceed.
+    fn quantile(&self, percent: u64) -> Duration {
+        let rank = (self.count * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::value(bucket);
+            }
+        }
+        Duration::ZERO
+    }
+}
+
+/// Collects the outcome of every request of a scan.
+pub struct Statistics {
+    started: Instant,
+    counters: Mutex<Counters>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            counters: Mutex::new(Counters::default()),
+        }
+    }
+
+    /// Continues counting from the totals of a checkpoint.
+    pub fn restore(counters: Counters) -> Self {
+        Self {
+            started: Instant::now(),
+            counters: Mutex::new(counters),
+        }
+    }
+
+    pub fn snapshot(&self) -> Counters {
+        let mut counters = self.counters.lock().unwrap().clone();
+        counters.elapsed += self.started.elapsed();
+        counters.latencies = Latencies::default();
+        counters
+    }
+
+    pub fn record_response(&self, status: u16, time: Duration, verdict: Verdict) {
+        let mut counters = self.counters.lock().unwrap();
+
+        counters.responses += 1;
+        match verdict {
+            Verdict::Matched => counters.matched += 1,
+            Verdict::Filtered => counters.filtered += 1,
+            Verdict::Duplicate => counters.duplicates += 1,
+        }
+        *counters.status_codes.entry(status).or_default() += 1;
+        counters.latencies.record(time);
+    }
+
+    pub fn record_error(&self, kind: ErrorKind) {
+        *self.counters.lock().unwrap().errors.entry(kind).or_default() += 1;
+    }
+
+    pub fn summary(&self) -> ScanSummary {
+        let counters = self.counters.lock().unwrap();
+        let elapsed = counters.elapsed + self.started.elapsed();
+
+        let errored = counters.errors.values().sum();
+        let total_requests = counters.responses + errored;
+
+        ScanSummary {
+            total_requests,
+            matched: counters.matched,
+            filtered: counters.filtered,
+            duplicates: counters.duplicates,
+            errored,
+            status_codes: counters.status_codes.clone(),
+            errors: counters.errors.clone(),
+            average_latency: counters.latencies.average(),
+            p95_latency: counters.latencies.quantile(95),
+            requests_per_second: total_requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            elapsed,
+            duplicate_groups: Vec::new(),
+            stopped: None,
+            checkpoint: None,
+            expired_jobs: Vec::new(),
+        }
+    }
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_cover_every_latency_in_order() {
+        let mut previous = 0;
+        for micros in (0..100_000).chain([u32::MAX as u64, u64::MAX - 1, u64::MAX]) {
+            let bucket = Latencies::bucket(micros);
+            assert!(bucket == previous || bucket == previous + 1 || micros >= 100_000, "{}", micros);
+            previous = bucket;
+
+            let value = Latencies::value(bucket).as_micros() as f64;
+            assert!((value - micros as f64).abs() <= micros as f64 / SUB_BUCKETS as f64, "{}", micros);
+        }
+        assert_eq!(Latencies::bucket(u64::MAX) + 1, 64 + 58 * 32);
+    }
+
+    #[test]
+    fn estimates_quantiles() {
+        let mut latencies = Latencies::default();
+        assert_eq!(latencies.quantile(95), Duration::ZERO);
+        assert_eq!(latencies.average(), Duration::ZERO);
+
+        for millis in 1..=1000 {
+            latencies.record(Duration::from_millis(millis));
+        }
+
+        assert_eq!(latencies.average(), Duration::from_micros(500_500));
+        let p95 = latencies.quantile(95).as_micros() as f64;
+        assert!((p95 - 950_000.0).abs() <= 950_000.0 / SUB_BUCKETS as f64, "{}", p95);
+        assert_eq!(latencies.quantile(0), Latencies::value(Latencies::bucket(1000)));
+    }
+
+    #[test]
+    fn keeps_exact_values_below_64_microseconds() {
+        let mut latencies = Latencies::default();
+        for micros in [10, 20, 30, 40] {
+            latencies.record(Duration::from_micros(micros));
+        }
+
+        assert_eq!(latencies.quantile(50), Duration::from_micros(20));
+        assert_eq!(latencies.quantile(95), Duration::from_micros(40));
+    }
+}
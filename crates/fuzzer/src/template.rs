@@ -0,0 +1,287 @@
+use crate::response::FuzzResponse;
+
+use colored::*;
+use serde::{Serialize, Serializer};
+
+/// Template used when none is given, matching the historical result line.
+pub const DEFAULT_TEMPLATE: &str = "({status})  {size:6} {time:8} {url}";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TemplateError {
+    UnclosedPlaceholder(usize),
+    UnknownField(usize),
+    InvalidWidth(usize),
+}
+
+impl TemplateError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateError::UnclosedPlaceholder(_) => "Unclosed `{` placeholder",
+            TemplateError::UnknownField(_) => "Unknown field",
+            TemplateError::InvalidWidth(_) => "Invalid field width",
+        }
+    }
+
+    /// 1-based column in the template where the error was detected.
+    pub fn column(&self) -> usize {
+        match *self {
+            TemplateError::UnclosedPlaceholder(c)
+            | TemplateError::UnknownField(c)
+            | TemplateError::InvalidWidth(c) => c,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TemplateField {
+    Url,
+    Target,
+    Payload,
+    Method,
+    Status,
+    Size,
+    Words,
+    Lines,
+    Time,
+    Redirect,
+    ContentType,
+    Title,
+    Header(String),
+}
+
+impl TemplateField {
+    pub fn from_name(name: &str) -> Option<TemplateField> {
+        let field = match name {
+            "url" => TemplateField::Url,
+            "target" => TemplateField::Target,
+            "payload" => TemplateField::Payload,
+            "method" => TemplateField::Method,
+            "status" => TemplateField::Status,
+            "size" => TemplateField::Size,
+            "words" => TemplateField::Words,
+            "lines" => TemplateField::Lines,
+            "time" => TemplateField::Time,
+            "redirect" => TemplateField::Redirect,
+            "content_type" => TemplateField::ContentType,
+            "title" => TemplateField::Title,
+            _ => match name.strip_prefix("header.") {
+                Some(header) if !header.is_empty() => TemplateField::Header(header.to_string()),
+                _ => return None,
+            },
+        };
+
+        Some(field)
+    }
+
+    fn value(&self, response: &FuzzResponse, method: &str) -> String {
+        match self {
+            TemplateField::Url => response.url.clone(),
+            TemplateField::Target => response.target.clone(),
+            TemplateField::Payload => response.payload.clone(),
+            TemplateField::Method => method.to_string(),
+            TemplateField::Status => response.status.to_string(),
+            TemplateField::Size => response.size.to_string(),
+            TemplateField::Words => response.words.to_string(),
+            TemplateField::Lines => response.lines.to_string(),
+            TemplateField::Time => format!("{}ms", response.time.as_millis()),
+            TemplateField::Redirect => response.redirect().unwrap_or_default().to_string(),
+            TemplateField::ContentType => response.content_type().unwrap_or_default().to_string(),
+            TemplateField::Title => response.title().unwrap_or_default(),
+            TemplateField::Header(name) => response.header(name).unwrap_or_default().to_string(),
+        }
+    }
+
+    fn colorize(&self, value: String, response: &FuzzResponse) -> ColoredString {
+        match self {
+            TemplateField::Status => match response.status {
+                200..=299 => value.green(),
+                300..=399 => value.blue(),
+                400..=499 => value.yellow(),
+                500..=599 => value.red(),
+                _ => value.white(),
+            },
+            TemplateField::Size if response.size == 0 => value.red().dimmed(),
+            TemplateField::Size => value.yellow().dimmed(),
+            TemplateField::Time => value.dimmed(),
+            TemplateField::Redirect => value.blue(),
+            TemplateField::Title => value.cyan(),
+            _ => value.normal(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Literal(String),
+    Field(TemplateField, Option<usize>),
+}
+
+/// A result line layout such as `{status} {size} {url} {header.server}`.
+/// `{field:N}` pads the value to N characters, `{{` and `}}` are literal braces.
+#[derive(Clone, Debug)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match (chars[i], chars.get(i + 1)) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    literal.push(chars[i]);
+                    i += 2;
+                }
+                ('{', _) => {
+                    let column = i + 1;
+                    let close = chars[i..]
+                        .iter()
+                        .position(|&c| c == '}')
+                        .ok_or(TemplateError::UnclosedPlaceholder(column))?;
+                    let placeholder: String = chars[i + 1..i + close].iter().collect();
+
+                    let (name, width) = match placeholder.split_once(':') {
+                        Some((name, width)) => {
+                            let width = width
+                                .parse()
+                                .map_err(|_| TemplateError::InvalidWidth(column))?;
+                            (name, Some(width))
+                        }
+                        None => (placeholder.as_str(), None),
+                    };
+                    let field = TemplateField::from_name(name.trim())
+                        .ok_or(TemplateError::UnknownField(column))?;
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field, width));
+                    i += close + 1;
+                }
+                (c, _) => {
+                    literal.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    pub fn render(&self, response: &FuzzResponse, method: &str, colored: bool) -> String {
+        let mut line = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => line.push_str(text),
+                Segment::Field(field, width) => {
+                    let mut value = field.value(response, method);
+                    if let Some(width) = width {
+                        value = format!("{:<width$}", value, width = width);
+                    }
+                    match colored {
+                        true => line.push_str(&field.colorize(value, response).to_string()),
+                        false => line.push_str(&value),
+                    }
+                }
+            }
+        }
+
+        line
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+impl Serialize for Template {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> FuzzResponse {
+        let mut response =
+            FuzzResponse::fixture(301, "http://target/admin", "admin", "<html><title> Admin\n  area </title></html>");
+        response.headers.insert("location", "/admin/".parse().unwrap());
+        response.headers.insert("server", "nginx".parse().unwrap());
+        response
+    }
+
+    fn render(source: &str) -> String {
+        Template::parse(source).unwrap().render(&response(), "GET", false)
+    }
+
+    #[test]
+    fn renders_fields_and_literals() {
+        assert_eq!(render("{method} {url} -> {status}"), "GET http://target/admin -> 301");
+        assert_eq!(render("{target}|{payload}|{time}"), "http://target/{}|admin|120ms");
+        assert_eq!(render("{redirect} {title} {header.server}"), "/admin/ Admin area nginx");
+        assert_eq!(render("{header.x-missing}{content_type}"), "");
+        assert_eq!(render("{ status }"), "301");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn pads_to_the_field_width() {
+        assert_eq!(render("[{status:5}]"), "[301  ]");
+        assert_eq!(render("[{url:4}]"), "[http://target/admin]");
+        assert_eq!(render("[{words:0}]"), "[4]");
+    }
+
+    #[test]
+    fn escapes_braces() {
+        assert_eq!(render("{{status}}"), "{status}");
+        assert_eq!(render("{{{status}}}"), "{301}");
+        assert_eq!(render("a } b"), "a } b");
+    }
+
+    #[test]
+    fn renders_the_default_template() {
+        assert_eq!(
+            Template::default().render(&response(), "GET", false),
+            "(301)  42     120ms    http://target/admin"
+        );
+    }
+
+    #[test]
+    fn keeps_its_source() {
+        assert_eq!(Template::parse("{url:10} {{x}}").unwrap().as_str(), "{url:10} {{x}}");
+    }
+
+    #[test]
+    fn reports_errors_with_their_column() {
+        let error = |source: &str| Template::parse(source).err().unwrap();
+
+        assert_eq!(error("{status} {url"), TemplateError::UnclosedPlaceholder(10));
+        assert_eq!(error("{code}"), TemplateError::UnknownField(1));
+        assert_eq!(error("ab {header.}"), TemplateError::UnknownField(4));
+        assert_eq!(error("{url{status}}"), TemplateError::UnknownField(1));
+        assert_eq!(error("{size:wide}"), TemplateError::InvalidWidth(1));
+        assert_eq!(error("{size:-1}"), TemplateError::InvalidWidth(1));
+        assert_eq!(error("é{size:}"), TemplateError::InvalidWidth(2));
+    }
+}
@@ -59,6 +59,8 @@ impl ReaderBuilder {
             reader: BufReader::with_capacity(chunk_size * LINE_MAX_SIZE, file),
             chunk_size,
             line_count,
+            position: 0,
+            lines_read: 0,
         };
 
         Ok(reader)
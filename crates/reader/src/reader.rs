@@ -1,16 +1,18 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Seek, SeekFrom},
 };
 
 pub enum ReaderError {
     EmptyChunk,
+    SeekFailed,
 }
 
 impl ReaderError {
     pub fn as_str(&self) -> &'static str {
         match self {
             ReaderError::EmptyChunk => "Empty chunk",
+            ReaderError::SeekFailed => "Wordlist position cannot be restored",
         }
     }
 }
@@ -19,6 +21,10 @@ pub struct Reader {
     pub reader: BufReader<File>,
     pub chunk_size: usize,
     pub line_count: usize,
+    /// Byte offset of the next line to read.
+    pub position: u64,
+    /// Number of lines read so far.
+    pub lines_read: usize,
 }
 
 impl Reader {
@@ -31,7 +37,11 @@ impl Reader {
 
             match self.reader.read_line(&mut line) {
                 Ok(0) => break,
-                Ok(_) => chunk.push(line.to_string()),
+                Ok(n) => {
+                    self.position += n as u64;
+                    self.lines_read += 1;
+                    chunk.push(line.to_string())
+                }
                 Err(_) => break,
             }
         }
@@ -41,4 +51,15 @@ impl Reader {
             false => Err(ReaderError::EmptyChunk),
         }
     }
+
+    /// Moves to a position previously read from `position`, `lines_read` lines into the file.
+    pub fn seek(&mut self, position: u64, lines_read: usize) -> Result<(), ReaderError> {
+        self.reader
+            .seek(SeekFrom::Start(position))
+            .map_err(|_| ReaderError::SeekFailed)?;
+
+        self.position = position;
+        self.lines_read = lines_read;
+        Ok(())
+    }
 }
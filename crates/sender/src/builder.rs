@@ -1,11 +1,16 @@
 use crate::Sender;
 
 use crate::SenderArgs;
-use reqwest::{Method, redirect::Policy};
+use reqwest::{
+    Method,
+    header::{ACCEPT, HeaderMap, HeaderValue, USER_AGENT},
+    redirect::Policy,
+};
 use std::{str::FromStr, time::Duration};
 
 pub enum SenderBuilderError {
     InvalidHTTPMethod,
+    InvalidUserAgent,
     Builder,
     HostUnreachable,
 }
@@ -14,6 +19,7 @@ impl SenderBuilderError {
     pub fn as_str(&self) -> &'static str {
         match self {
             SenderBuilderError::InvalidHTTPMethod => "Method specified is not a valid HTTP method",
+            SenderBuilderError::InvalidUserAgent => "User agent specified is not a valid header value",
             SenderBuilderError::Builder => "TLS backend cannot be initialized, or the resolver cannot load the system configuration",
             SenderBuilderError::HostUnreachable => "Specified host is unreachable",
         }
@@ -75,9 +81,16 @@ impl SenderBuilder {
             false => Policy::none(),
         };
 
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_str(&self.user_agent).map_err(|_| SenderBuilderError::InvalidUserAgent)?,
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+
         let client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_millis(self.request_timeout))
-            .user_agent(&self.user_agent)
+            .default_headers(headers.clone())
             .redirect(policy)
             .build()
             .map_err(|_| SenderBuilderError::Builder)?;
@@ -85,6 +98,7 @@ impl SenderBuilder {
         let sender = Sender {
             client,
             method,
+            headers,
             args: SenderArgs {
                 user_agent: self.user_agent.clone(),
                 request_timeout: self.request_timeout,
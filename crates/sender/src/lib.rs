@@ -1,4 +1,5 @@
 pub mod builder;
+pub mod preflight;
 pub mod sender;
 
 pub use builder::SenderBuilder;
@@ -11,3 +12,9 @@ pub struct SenderArgs {
     pub follow_redirect: bool,
     pub method: String,
 }
+
+/// Whether the payload of `template` goes in the host name, every payload making a different host.
+pub fn is_host_template(template: &str) -> bool {
+    let authority = template.split_once("://").map_or(template, |(_, rest)| rest);
+    authority.split(['/', '?', '#']).next().is_some_and(|host| host.contains("{}"))
+}
@@ -0,0 +1,198 @@
+use crate::{Sender, is_host_template};
+
+use native_tls::TlsConnector;
+use reqwest::Url;
+use std::{
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+pub enum PreflightError {
+    InvalidUrl,
+    DnsFailed,
+    ConnectFailed,
+    TlsFailed,
+    RequestFailed,
+}
+
+impl PreflightError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreflightError::InvalidUrl => "Target URL is not a valid http or https URL",
+            PreflightError::DnsFailed => "Target host name cannot be resolved",
+            PreflightError::ConnectFailed => "Target host does not accept connections on this port",
+            PreflightError::TlsFailed => "TLS handshake with the target host failed",
+            PreflightError::RequestFailed => "Baseline request to the target failed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PreflightStep {
+    Dns,
+    Connect,
+    Tls,
+    Request,
+}
+
+impl PreflightStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreflightStep::Dns => "DNS",
+            PreflightStep::Connect => "TCP connect",
+            PreflightStep::Tls => "TLS",
+            PreflightStep::Request => "Baseline",
+        }
+    }
+
+    fn error(&self) -> PreflightError {
+        match self {
+            PreflightStep::Dns => PreflightError::DnsFailed,
+            PreflightStep::Connect => PreflightError::ConnectFailed,
+            PreflightStep::Tls => PreflightError::TlsFailed,
+            PreflightStep::Request => PreflightError::RequestFailed,
+        }
+    }
+}
+
+/// Outcome of one preflight step, `detail` telling what was found or what went wrong.
+/// A skipped step did not run, and counts as passed.
+pub struct PreflightCheck {
+    pub step: PreflightStep,
+    pub passed: bool,
+    pub skipped: bool,
+    pub detail: String,
+}
+
+impl PreflightCheck {
+    fn passed(step: PreflightStep, detail: String) -> Self {
+        Self {
+            step,
+            passed: true,
+            skipped: false,
+            detail,
+        }
+    }
+
+    fn failed(step: PreflightStep, detail: String) -> Self {
+        Self {
+            step,
+            passed: false,
+            skipped: false,
+            detail,
+        }
+    }
+
+    fn skipped(step: PreflightStep, detail: &str) -> Self {
+        Self {
+            step,
+            passed: true,
+            skipped: true,
+            detail: detail.to_string(),
+        }
+    }
+
+    pub fn error(&self) -> Option<PreflightError> {
+        (!self.passed).then(|| self.step.error())
+    }
+}
+
+impl Sender {
+    /// Checks that `template` can be scanned: its host resolves, accepts connections,
+    /// completes a TLS handshake for https, and answers a request with an empty payload.
+    ///
+    /// Steps run in that order and stop at the first failure, which is the last check returned.
+    /// A URL that is not http or https is an error before any step. When the payload goes
+    /// in the host name, every host is a different one, and the steps are skipped.
+    pub fn preflight(&self, template: &str) -> Result<Vec<PreflightCheck>, PreflightError> {
+        let host_template = is_host_template(template);
+        let url = match host_template {
+            true => template.replace("{}", "rake"),
+            false => template.replace("{}", ""),
+        };
+        let parsed = Url::parse(&url).map_err(|_| PreflightError::InvalidUrl)?;
+        let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+            return Err(PreflightError::InvalidUrl);
+        };
+        let https = match parsed.scheme() {
+            "https" => true,
+            "http" => false,
+            _ => return Err(PreflightError::InvalidUrl),
+        };
+        // IPv6 hosts come bracketed, which neither the resolver nor TLS expect.
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let timeout = Duration::from_millis(self.args.request_timeout);
+
+        if host_template {
+            let mut steps = vec![PreflightStep::Dns, PreflightStep::Connect, PreflightStep::Request];
+            if https {
+                steps.insert(2, PreflightStep::Tls);
+            }
+            return Ok(steps
+                .into_iter()
+                .map(|step| PreflightCheck::skipped(step, "the host depends on the payload"))
+                .collect());
+        }
+
+        let mut checks = Vec::new();
+
+        let addresses: Vec<SocketAddr> = match (host, port).to_socket_addrs() {
+            Ok(addresses) => addresses.collect(),
+            Err(e) => {
+                checks.push(PreflightCheck::failed(PreflightStep::Dns, e.to_string()));
+                return Ok(checks);
+            }
+        };
+        let Some(&address) = addresses.first() else {
+            checks.push(PreflightCheck::failed(PreflightStep::Dns, String::from("no address found")));
+            return Ok(checks);
+        };
+        checks.push(PreflightCheck::passed(PreflightStep::Dns, format!("{} -> {}", host, address.ip())));
+
+        let started = Instant::now();
+        let stream = match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => stream,
+            Err(e) => {
+                checks.push(PreflightCheck::failed(PreflightStep::Connect, format!("{}: {}", address, e)));
+                return Ok(checks);
+            }
+        };
+        checks.push(PreflightCheck::passed(
+            PreflightStep::Connect,
+            format!("{} in {}ms", address, started.elapsed().as_millis()),
+        ));
+
+        if https {
+            let _ = stream.set_read_timeout(Some(timeout));
+            let _ = stream.set_write_timeout(Some(timeout));
+
+            let started = Instant::now();
+            let handshake = TlsConnector::new()
+                .map_err(|e| e.to_string())
+                .and_then(|connector| connector.connect(host, stream).map_err(|e| e.to_string()));
+            match handshake {
+                Ok(_) => checks.push(PreflightCheck::passed(
+                    PreflightStep::Tls,
+                    format!("handshake in {}ms", started.elapsed().as_millis()),
+                )),
+                Err(e) => {
+                    checks.push(PreflightCheck::failed(PreflightStep::Tls, e));
+                    return Ok(checks);
+                }
+            }
+        } else {
+            // A server handling one connection at a time would not answer the baseline request otherwise.
+            drop(stream);
+        }
+
+        match self.send(&url) {
+            Ok(exchange) => checks.push(PreflightCheck::passed(
+                PreflightStep::Request,
+                format!("{} {} in {}ms", self.method, exchange.response.status(), exchange.time.as_millis()),
+            )),
+            Err(e) => checks.push(PreflightCheck::failed(PreflightStep::Request, e.to_string())),
+        }
+
+        Ok(checks)
+    }
+}
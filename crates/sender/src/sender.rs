@@ -2,6 +2,7 @@ use crate::SenderArgs;
 use reqwest::{
     Error, Method,
     blocking::{Client, Response},
+    header::HeaderMap,
 };
 use std::time::{Duration, SystemTime};
 
@@ -9,17 +10,50 @@ pub struct Sender {
     pub client: Client,
     pub args: SenderArgs,
     pub method: Method,
+    /// Headers set on every request. The connection adds `Host` on top of them.
+    pub headers: HeaderMap,
+}
+
+/// A response, with the request headers it answers and the time it took.
+pub struct Exchange {
+    pub request_headers: HeaderMap,
+    pub response: Response,
+    pub time: Duration,
 }
 
 impl Sender {
+    /// Whether every preflight step passes for the target URL.
     pub fn is_reachable(&self) -> bool {
-        self.client.get(&self.args.url).send().is_ok()
+        self.preflight(&self.args.url)
+            .is_ok_and(|checks| checks.iter().all(|check| check.passed))
+    }
+
+    pub fn send(&self, url: &str) -> Result<Exchange, Error> {
+        self.execute(self.method.clone(), url)
     }
 
-    pub fn send(&self, url: &String) -> Result<(Response, Duration), Error> {
+    /// Sends a GET request with the configured headers, whatever the method of the scan,
+    /// to read a file of the target such as `robots.txt`.
+    pub fn get(&self, url: &str) -> Result<Exchange, Error> {
+        self.execute(Method::GET, url)
+    }
+
+    fn execute(&self, method: Method, url: &str) -> Result<Exchange, Error> {
+        let request = self
+            .client
+            .request(method, url)
+            .headers(self.headers.clone())
+            .build()?;
+        let request_headers = request.headers().clone();
+
         let now = SystemTime::now();
-        let response = self.client.request(self.method.clone(), url).send()?;
-        let elapsed = now.elapsed().unwrap();
-        Ok((response, elapsed))
+        let response = self.client.execute(request)?;
+        let time = now.elapsed().unwrap();
+
+        Ok(Exchange {
+            request_headers,
+            response,
+            time,
+        })
     }
 }
@@ -1,9 +1,19 @@
-use clap::Parser;
-use fuzzer::FuzzerArgs;
+use clap::{Parser, ValueEnum};
+use fuzzer::{
+    FuzzerArgs,
+    expression::Expression,
+    filter::FilterMode,
+    limits::Limits,
+    order::{GroupKey, ResultOrder, SortKey},
+    recursion::RecursionStrategy,
+    output::{Column, OutputFormat, OutputTarget},
+    template::Template,
+};
 use std::{
     convert::TryFrom,
     fmt::{Debug, Display},
     str::FromStr,
+    time::Duration,
 };
 
 #[derive(Clone)]
@@ -12,15 +22,78 @@ pub enum RangeOrValue<T> {
     Range(T, T),
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Mode {
+    And,
+    Or,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Json,
+    Jsonl,
+    Csv,
+    Md,
+    Html,
+    Txt,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Sort {
+    Status,
+    Size,
+    Url,
+    Time,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Group {
+    Status,
+    Size,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Strategy {
+    Default,
+    Greedy,
+}
+
+/// Status code filtered out when no status filter, matcher nor expression is given.
+const DEFAULT_FILTERED_CODE: u16 = 404;
+
+/// URL templates read from a target list file.
+#[derive(Clone)]
+pub struct TargetList(Vec<String>);
+
 #[derive(Parser)]
 pub struct ArgsSchema {
+    /// Configuration file to read options from.
+    /// Keys are the long option names, e.g. `threads = 20` or `filter-code = [404, "500-599"]`,
+    /// and `[profiles.<name>]` tables hold named sets of options applied with `--profile`.
+    /// Options given on the command line take precedence, `--url` and `--url-list` overriding each other,
+    /// and `--no-<flag>` turns off a flag the file sets, e.g. `--no-dedupe`.
+    /// Default is `~/.config/rake/config.toml`, when it exists
+    #[arg(long = "config")]
+    pub config: Option<String>,
+
+    /// Profile of the configuration file to apply, on top of its top-level options.
+    #[arg(long = "profile")]
+    pub profile: Option<String>,
+
     /// Target URL to fuzz.
     /// The `{}` placeholder in the URL will be replaced by payloads.
     ///
     /// Example: `https://rake.io/pages/{}`
     #[arg(short = 'u', long = "url")]
-    #[arg(required = true)]
-    pub url: String,
+    #[arg(required_unless_present = "target_list")]
+    pub url: Option<String>,
+
+    /// File of target URLs to fuzz, one URL template per line.
+    /// Targets are scanned concurrently and share the threads, each host in turn.
+    /// Empty lines and lines starting with `#` are ignored.
+    #[arg(short = 'U', long = "url-list")]
+    #[arg(value_parser(parse_target_list))]
+    pub target_list: Option<TargetList>,
 
     /// Path to the wordlist file.
     #[arg(short = 'w', long = "wordlist")]
@@ -34,6 +107,12 @@ pub struct ArgsSchema {
     #[arg(value_parser = clap::value_parser!(u8).range(1..=120))]
     pub threads: u8,
 
+    /// Maximum number of requests per second, shared by every thread.
+    /// Default is 0, no limit
+    #[arg(long = "rate")]
+    #[arg(default_value_t = 0, hide_default_value = true)]
+    pub rate: u32,
+
     /// Request timeout in milliseconds.
     /// Default is 5000 ms (5 seconds).
     #[arg(short = 'T', long = "timeout")]
@@ -49,11 +128,10 @@ pub struct ArgsSchema {
     /// List of HTTP status codes to ignore.
     ///
     /// Example: `200-300, 401` will filter responses with status beetwen 200 and 300, and 401.
-    /// Default is: 404
+    /// Default is: 404, unless a matcher or `--expr` is given, so that it does not hide what they select
     #[arg(short = 'c', long = "filter-code")]
     #[arg(num_args = 1.., value_delimiter = ',')]
     #[arg(value_parser(parse_range_or_value::<u16>))]
-    #[arg(default_value = "404", hide_default_value = true)]
     pub filtered_code: Vec<RangeOrValue<u16>>,
     
     /// List of word contained in body to filter.
@@ -94,6 +172,235 @@ pub struct ArgsSchema {
     #[arg(value_parser(parse_range_or_value::<u64>))]
     pub matched_size: Vec<RangeOrValue<u64>>,
 
+    /// Expression every shown response must satisfy, on top of matchers and filters.
+    ///
+    /// Fields: status, size, words, lines, time (ms), body, url, payload, header.<name>.
+    /// Operators: == != < <= > >= in ~ !~ && || ! and parentheses.
+    ///
+    /// Example: `status in 200..300 && size > 1000 && !body ~ /not found/i && time < 500`
+    #[arg(short = 'e', long = "expr")]
+    #[arg(value_parser(parse_expression))]
+    pub expression: Option<Expression>,
+
+    /// How matchers are combined.
+    ///
+    /// `and` keeps responses selected by every matcher, `or` by at least one.
+    /// Default is or
+    #[arg(long = "match-mode")]
+    #[arg(value_enum, default_value_t = Mode::Or, hide_default_value = true)]
+    pub match_mode: Mode,
+
+    /// How filters are combined.
+    ///
+    /// `and` drops responses caught by every filter, `or` by at least one.
+    /// Filters are applied after matchers.
+    /// Default is or
+    #[arg(long = "filter-mode")]
+    #[arg(value_enum, default_value_t = Mode::Or, hide_default_value = true)]
+    pub filter_mode: Mode,
+
+    /// Learn the target's "not found" response before the scan.
+    ///
+    /// Random payloads are sent first, and responses sharing the status, size,
+    /// word count and line count they consistently produced are filtered out.
+    /// Default is false
+    #[arg(long = "auto-calibrate")]
+    #[arg(default_value_t = false)]
+    pub auto_calibrate: bool,
+
+    /// Do not check the targets before the scan.
+    ///
+    /// By default the host of each target is resolved and connected to, TLS is negotiated
+    /// for https, and one request is sent, the scan being aborted if any step fails.
+    /// Default is false
+    #[arg(long = "skip-preflight")]
+    #[arg(default_value_t = false)]
+    pub skip_preflight: bool,
+
+    /// Filter responses at least this similar (in percent) to a calibration baseline.
+    ///
+    /// Bodies are compared token by token once the payload is stripped from them,
+    /// which catches soft-404 pages that reflect the requested path.
+    ///
+    /// Example: `90` will filter responses 90% similar or more to the baseline.
+    #[arg(long = "filter-similarity", requires = "auto_calibrate")]
+    #[arg(value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub similarity: Option<u8>,
+
+    /// Show each distinct response only once.
+    ///
+    /// Responses with the same status and body, once the payload is removed,
+    /// are collapsed and counted in the end-of-scan summary.
+    /// Default is false
+    #[arg(long = "dedupe")]
+    #[arg(default_value_t = false)]
+    pub dedupe: bool,
+
+    /// Maximum depth of recursive scans into the directories found.
+    /// Each directory result is scanned again with the same wordlist, as `dir/{}`.
+    /// Default is 0 (no recursion)
+    #[arg(long = "recursion-depth")]
+    #[arg(default_value_t = 0, hide_default_value = true)]
+    pub recursion_depth: usize,
+
+    /// List of HTTP status codes of responses that start a recursive scan,
+    /// shown or not: filtering out 301 or 403 does not stop recursion into them.
+    ///
+    /// Example: `301-302, 403`
+    /// Default is: 301,302,307,308,403
+    #[arg(long = "recursion-status")]
+    #[arg(num_args = 1.., value_delimiter = ',')]
+    #[arg(value_parser(parse_range_or_value::<u16>))]
+    #[arg(default_value = "301,302,307,308,403", hide_default_value = true)]
+    pub recursion_status: Vec<RangeOrValue<u16>>,
+
+    /// Which results start a recursive scan.
+    ///
+    /// `default` only recurses into results that look like directories: a redirect to the
+    /// same path with a trailing slash, or a URL ending with a slash.
+    /// `greedy` recurses into every result with a recursion status.
+    /// Default is default
+    #[arg(long = "recursion-strategy")]
+    #[arg(value_enum, default_value_t = Strategy::Default, hide_default_value = true)]
+    pub recursion_strategy: Strategy,
+
+    /// Request the links of matched HTML responses too: `href`, `src` and `action` attributes,
+    /// and URLs quoted in inline scripts. Only links on the origin of the target are followed,
+    /// each once. Their results are marked as discovered.
+    /// Links in the pages found this way are followed too, up to `--recursion-depth` levels, at least one.
+    /// Default is false
+    #[arg(long = "extract-links")]
+    #[arg(default_value_t = false)]
+    pub extract_links: bool,
+
+    /// Fetch `/robots.txt` and `/sitemap.xml` of each target, and the sitemaps they point to,
+    /// before the scan. Every path they disclose is requested once, and its last segment
+    /// is added to the wordlist.
+    /// Default is false
+    #[arg(long = "seed")]
+    #[arg(default_value_t = false)]
+    pub seed: bool,
+
+    /// Print the results again at scan end, sorted by this field, and write output files in that order.
+    /// Results still stream live on the terminal; piped output only holds the sorted results.
+    #[arg(long = "sort")]
+    #[arg(value_enum)]
+    pub sort: Option<Sort>,
+
+    /// Group the results printed at scan end, and in output files, by this field.
+    /// Size groups are listed from the rarest to the most common size, to spot outliers.
+    #[arg(long = "group-by")]
+    #[arg(value_enum)]
+    pub group_by: Option<Group>,
+
+    /// Stop the scan after this many seconds. With `--checkpoint`, it can be resumed from the checkpoint saved on stopping.
+    #[arg(long = "max-time")]
+    pub max_time: Option<u64>,
+
+    /// Stop the scan after this many requests. With `--checkpoint`, it can be resumed from the checkpoint saved on stopping.
+    #[arg(long = "max-requests")]
+    pub max_requests: Option<usize>,
+
+    /// Abandon each target or recursive scan after this many seconds, the other scans going on.
+    #[arg(long = "max-time-per-job")]
+    pub max_time_per_job: Option<u64>,
+
+    /// Stop the scan when more than this percentage of the last 100 responses are 403,
+    /// which usually means a WAF started blocking the scan.
+    #[arg(long = "stop-on-403")]
+    #[arg(value_parser = clap::value_parser!(u8).range(0..100))]
+    pub stop_on_403: Option<u8>,
+
+    /// Stop the scan when every request failed for this many seconds.
+    #[arg(long = "stop-on-errors")]
+    pub stop_on_errors: Option<u64>,
+
+    /// Stop the scan on the first result.
+    /// Default is false
+    #[arg(long = "stop-on-match")]
+    #[arg(default_value_t = false)]
+    pub stop_on_match: bool,
+
+    /// Files to write the results to.
+    /// Several files can be written at once, each in its own format.
+    ///
+    /// Example: `results.json,report.md,report.html,results.txt`
+    ///
+    /// Long form only: `-o` is already `--filter-word`.
+    #[arg(long = "output")]
+    #[arg(num_args = 1.., value_delimiter = ',')]
+    pub outputs: Vec<String>,
+
+    /// Directory to store the raw request and response of every result in.
+    /// Files are named by a hash of the request, and `index.txt` maps URLs to files.
+    #[arg(long = "output-dir")]
+    pub output_dir: Option<String>,
+
+    /// File to save the scan state to every 10 seconds, to continue it later with `--resume`.
+    /// It holds the wordlist position of every job and the pending recursive scans,
+    /// the results so far being appended to the same path with a `.results` extension.
+    /// Without it, an interrupted scan is still saved, to `rake.checkpoint.json` or the first free
+    /// `rake.checkpoint.N.json`, its results being kept without their bodies.
+    #[arg(long = "checkpoint")]
+    pub checkpoint: Option<String>,
+
+    /// Continue the scan saved in this checkpoint file, and keep saving to it.
+    /// Targets and wordlist must be the same as in the interrupted scan.
+    #[arg(long = "resume")]
+    pub resume: Option<String>,
+
+    /// Pause the scan when Enter is pressed, and open a prompt to change filters,
+    /// show the queue and counters, change the rate, or stop.
+    /// Ignored when stdin is not a terminal. Default is false
+    #[arg(long = "interactive")]
+    #[arg(default_value_t = false)]
+    pub interactive: bool,
+
+    /// Format of the output files whose extension does not tell it.
+    /// Known extensions (json, jsonl, csv, md, html, txt) always set the format of their file.
+    /// Default is json
+    #[arg(long = "format", requires = "outputs")]
+    #[arg(value_enum)]
+    pub format: Option<Format>,
+
+    /// Columns of CSV and Markdown output files.
+    ///
+    /// Available: url, target, payload, method, status, size, words, lines, duration, redirect, content_type.
+    /// Default is url,status,size,words,lines,duration
+    #[arg(long = "columns")]
+    #[arg(num_args = 1.., value_delimiter = ',')]
+    #[arg(value_parser(parse_column))]
+    pub columns: Vec<Column>,
+
+    /// Print only the URLs of the results, without banner nor progress bar.
+    /// Default is false
+    #[arg(short = 'q', long = "silent")]
+    #[arg(default_value_t = false)]
+    pub silent: bool,
+
+    /// Layout of result lines, on the terminal and in txt output files.
+    ///
+    /// Fields: url, target, payload, method, status, size, words, lines, time, redirect,
+    /// content_type, title, header.<name>. `{field:N}` pads the value to N characters.
+    ///
+    /// Example: `{status} {size} {url} {header.server}`
+    #[arg(long = "output-template")]
+    #[arg(value_parser(parse_template))]
+    pub template: Option<Template>,
+
+    /// Show the redirect target, content type, page title and payload on result lines.
+    /// Default is false
+    #[arg(short = 'v', long = "verbose")]
+    #[arg(default_value_t = false)]
+    pub verbose: bool,
+
+    /// Disable colours.
+    /// Colours are also disabled when stdout is not a terminal or `NO_COLOR` is set.
+    /// Default is false
+    #[arg(long = "no-color")]
+    #[arg(default_value_t = false)]
+    pub no_color: bool,
+
     /// Follow redirects.
     /// Default is false
     #[arg(short = 'r', long = "follow-redirect")]
@@ -137,6 +444,50 @@ where
     }
 }
 
+pub fn parse_expression(s: &str) -> Result<Expression, String> {
+    Expression::parse(s).map_err(|e| {
+        format!(
+            "{} at column {}\n\n    {}\n    {}^",
+            e.as_str(),
+            e.column(),
+            s,
+            " ".repeat(e.column() - 1)
+        )
+    })
+}
+
+pub fn parse_template(s: &str) -> Result<Template, String> {
+    Template::parse(s).map_err(|e| {
+        format!(
+            "{} at column {}\n\n    {}\n    {}^",
+            e.as_str(),
+            e.column(),
+            s,
+            " ".repeat(e.column() - 1)
+        )
+    })
+}
+
+pub fn parse_target_list(path: &str) -> Result<TargetList, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Cannot read target list: {}", e))?;
+
+    let targets: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    match targets.is_empty() {
+        true => Err(String::from("Target list is empty")),
+        false => Ok(TargetList(targets)),
+    }
+}
+
+pub fn parse_column(s: &str) -> Result<Column, String> {
+    Column::from_name(s.trim()).ok_or_else(|| format!("Unknown column: {}", s.trim()))
+}
+
 pub fn expand_ranges<T>(input: Vec<RangeOrValue<T>>) -> Vec<T>
 where
     T: Into<u64> + TryFrom<u64>,
@@ -156,22 +507,174 @@ where
         .collect()
 }
 
+impl From<Mode> for FilterMode {
+    fn from(mode: Mode) -> FilterMode {
+        match mode {
+            Mode::And => FilterMode::And,
+            Mode::Or => FilterMode::Or,
+        }
+    }
+}
+
+impl From<Format> for OutputFormat {
+    fn from(format: Format) -> OutputFormat {
+        match format {
+            Format::Json => OutputFormat::Json,
+            Format::Jsonl => OutputFormat::Jsonl,
+            Format::Csv => OutputFormat::Csv,
+            Format::Md => OutputFormat::Md,
+            Format::Html => OutputFormat::Html,
+            Format::Txt => OutputFormat::Txt,
+        }
+    }
+}
+
+impl From<Strategy> for RecursionStrategy {
+    fn from(strategy: Strategy) -> RecursionStrategy {
+        match strategy {
+            Strategy::Default => RecursionStrategy::Default,
+            Strategy::Greedy => RecursionStrategy::Greedy,
+        }
+    }
+}
+
+impl From<Sort> for SortKey {
+    fn from(sort: Sort) -> SortKey {
+        match sort {
+            Sort::Status => SortKey::Status,
+            Sort::Size => SortKey::Size,
+            Sort::Url => SortKey::Url,
+            Sort::Time => SortKey::Time,
+        }
+    }
+}
+
+impl From<Group> for GroupKey {
+    fn from(group: Group) -> GroupKey {
+        match group {
+            Group::Status => GroupKey::Status,
+            Group::Size => GroupKey::Size,
+        }
+    }
+}
+
 impl From<ArgsSchema> for FuzzerArgs {
     fn from(args: ArgsSchema) -> FuzzerArgs {
+        let outputs = args
+            .outputs
+            .into_iter()
+            .map(|path| OutputTarget {
+                format: OutputFormat::from_path(&path)
+                    .or(args.format.map(OutputFormat::from))
+                    .unwrap_or(OutputFormat::Json),
+                path,
+            })
+            .collect();
+
+        let mut targets: Vec<String> = args.url.into_iter().collect();
+        for target in args.target_list.map(|list| list.0).unwrap_or_default() {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+
+        // The default filter only applies to scans that select nothing explicitly.
+        let selects = !args.matched_code.is_empty()
+            || !args.matched_size.is_empty()
+            || !args.matched_word.is_empty()
+            || args.expression.is_some();
+        let filtered_code = match (args.filtered_code.is_empty(), selects) {
+            (true, false) => vec![DEFAULT_FILTERED_CODE],
+            _ => expand_ranges(args.filtered_code),
+        };
+
+        let columns = match args.columns.is_empty() {
+            true => Column::DEFAULT.to_vec(),
+            false => args.columns,
+        };
+
         FuzzerArgs {
-            url: args.url,
+            targets,
             wordlist: args.wordlist,
             threads: args.threads,
+            rate: args.rate,
             timeout: args.timeout,
             user_agent: args.user_agent,
-            filtered_code: expand_ranges(args.filtered_code),
+            filtered_code,
             filtered_size: expand_ranges(args.filtered_size),
             filtered_word: args.filtered_word,
             matched_code: expand_ranges(args.matched_code),
             matched_size: expand_ranges(args.matched_size),
             matched_word: args.matched_word,
+            expression: args.expression,
+            match_mode: args.match_mode.into(),
+            filter_mode: args.filter_mode.into(),
+            auto_calibrate: args.auto_calibrate,
+            skip_preflight: args.skip_preflight,
+            similarity: args.similarity,
+            dedupe: args.dedupe,
+            recursion_depth: args.recursion_depth,
+            recursion_status: expand_ranges(args.recursion_status),
+            recursion_strategy: args.recursion_strategy.into(),
+            extract_links: args.extract_links,
+            seed: args.seed,
+            order: ResultOrder {
+                sort: args.sort.map(SortKey::from),
+                group_by: args.group_by.map(GroupKey::from),
+            },
+            limits: Limits {
+                max_time: args.max_time.map(Duration::from_secs),
+                max_requests: args.max_requests,
+                max_time_per_job: args.max_time_per_job.map(Duration::from_secs),
+                blocked_percent: args.stop_on_403,
+                error_time: args.stop_on_errors.map(Duration::from_secs),
+                stop_on_match: args.stop_on_match,
+            },
+            template: args.template,
+            silent: args.silent,
+            verbose: args.verbose,
+            no_color: args.no_color,
             follow_redirect: args.follow_redirect,
+            outputs,
+            columns,
+            output_dir: args.output_dir,
+            checkpoint: args.checkpoint.or_else(|| args.resume.clone()),
+            resume: args.resume,
+            interactive: args.interactive,
             method: args.method,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(options: &[&str]) -> FuzzerArgs {
+        let cli = ["rake", "-u", "http://target/{}", "-w", "words.txt"];
+        FuzzerArgs::from(ArgsSchema::parse_from(cli.iter().chain(options)))
+    }
+
+    #[test]
+    fn filters_404_by_default() {
+        assert_eq!(parse(&[]).filtered_code, [404]);
+        assert_eq!(parse(&["-s", "0"]).filtered_code, [404]);
+    }
+
+    #[test]
+    fn matchers_drop_the_default_filter() {
+        let args = parse(&["-C", "404"]);
+        assert!(args.filtered_code.is_empty());
+        assert_eq!(args.matched_code, [404]);
+
+        assert!(parse(&["-S", "120"]).filtered_code.is_empty());
+        assert!(parse(&["-O", "admin"]).filtered_code.is_empty());
+        assert!(parse(&["--expr", "status == 404"]).filtered_code.is_empty());
+    }
+
+    #[test]
+    fn keeps_explicit_filters() {
+        assert_eq!(parse(&["-C", "200-299", "-c", "204"]).filtered_code, [204]);
+        assert_eq!(parse(&["-c", "404,500-502"]).filtered_code, [404, 500, 501, 502]);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::args::ArgsSchema;
+
+use clap::{Command, CommandFactory, Parser, error::ErrorKind, parser::ValueSource};
+use std::{env, ffi::OsString, fs, path::PathBuf};
+use toml::{Table, Value};
+
+/// Table of the configuration file holding the named profiles.
+const PROFILES: &str = "profiles";
+
+/// Options that set the same thing: the command line setting one of them overrides them all.
+const GROUPS: [&[&str]; 1] = [&["url", "url-list"]];
+
+/// Parses the command line, completed with the options of the configuration file.
+///
+/// Options are read from the top level of the file, then from the selected profile,
+/// then from the command line, each overriding the previous ones.
+pub fn parse() -> ArgsSchema {
+    match merge(env::args_os().collect(), default_path()) {
+        Ok(args) => ArgsSchema::parse_from(args),
+        Err(message) => ArgsSchema::command().error(ErrorKind::InvalidValue, message).exit(),
+    }
+}
+
+/// Arguments of the command line preceded by the options of the configuration file it does not set.
+/// The file is the one given with `--config`, or `default` when it exists.
+fn merge(cli: Vec<OsString>, default: Option<PathBuf>) -> Result<Vec<OsString>, String> {
+    let command = ArgsSchema::command();
+
+    // `--no-<flag>` turns off a flag set in the file, and is not an argument of its own.
+    let mut negated = Vec::new();
+    let cli: Vec<OsString> = cli
+        .into_iter()
+        .filter(|arg| match negated_flag(&command, arg) {
+            Some(flag) => {
+                negated.push(flag);
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    // A first lenient pass finds the configuration file, and the options the command line sets.
+    let matches = command.clone().ignore_errors(true).get_matches_from(&cli);
+
+    let path = match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => default.filter(|path| path.exists()),
+    };
+    let profile = matches.get_one::<String>("profile");
+
+    let Some(path) = path else {
+        if let Some(profile) = profile {
+            return Err(format!("Profile `{}` is set but there is no configuration file", profile));
+        }
+        return Ok(cli);
+    };
+
+    let options = load(&path, profile.map(String::as_str))?;
+    let set_by_cli = |key: &str| {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key))
+            .is_some_and(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+    };
+
+    let mut args = vec![cli[0].clone()];
+    for (key, value) in &options {
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_long() == Some(key.as_str())) else {
+            return Err(format!("Unknown option `{}` in {}", key, path.display()));
+        };
+        if matches!(key.as_str(), "config" | "profile") {
+            return Err(format!("`{}` cannot be set in a configuration file", key));
+        }
+        let group = GROUPS.iter().find(|group| group.contains(&key.as_str()));
+        let overridden = match group {
+            Some(group) => group.iter().any(|key| set_by_cli(key)),
+            None => set_by_cli(key),
+        };
+        if overridden || negated.contains(key) {
+            continue;
+        }
+        args.extend(to_args(key, value, arg.get_action().takes_values())?);
+    }
+    args.extend(cli.into_iter().skip(1));
+
+    Ok(args)
+}
+
+/// Name of the flag `arg` turns off, when it is `--no-<flag>`.
+fn negated_flag(command: &Command, arg: &OsString) -> Option<String> {
+    let name = arg.to_str()?.strip_prefix("--no-")?;
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(name) && !arg.get_action().takes_values())
+        .map(|_| name.to_string())
+}
+
+/// `$XDG_CONFIG_HOME/rake/config.toml`, or `~/.config/rake/config.toml`.
+fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join("rake").join("config.toml"))
+}
+
+/// Top-level options of the file, overridden by those of `profile`.
+fn load(path: &PathBuf, profile: Option<&str>) -> Result<Table, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Configuration file {} cannot be read: {}", path.display(), e))?;
+    let mut options: Table = content
+        .parse()
+        .map_err(|e| format!("Configuration file {} is not valid TOML: {}", path.display(), e))?;
+
+    let profiles = match options.remove(PROFILES) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => return Err(format!("`{}` must be a table of profiles in {}", PROFILES, path.display())),
+        None => Table::new(),
+    };
+
+    if let Some(name) = profile {
+        match profiles.get(name) {
+            Some(Value::Table(overrides)) => options.extend(overrides.clone()),
+            Some(_) => return Err(format!("Profile `{}` must be a table in {}", name, path.display())),
+            None => return Err(format!("Profile `{}` not found in {}", name, path.display())),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Command line arguments setting `key` to `value`: the flag alone for `true`,
+/// and one `--key=value` occurrence per element of an array.
+fn to_args(key: &str, value: &Value, takes_values: bool) -> Result<Vec<OsString>, String> {
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        _ => Err(format!("`{}` must be a string, a number, or an array of them", key)),
+    };
+
+    if !takes_values {
+        return match value {
+            Value::Boolean(true) => Ok(vec![OsString::from(format!("--{}", key))]),
+            Value::Boolean(false) => Ok(Vec::new()),
+            _ => Err(format!("`{}` is a flag and must be true or false", key)),
+        };
+    }
+
+    let values = match value {
+        Value::Array(values) => values.iter().map(scalar).collect::<Result<Vec<_>, _>>()?,
+        value => vec![scalar(value)?],
+    };
+
+    Ok(values
+        .into_iter()
+        .map(|value| OsString::from(format!("--{}={}", key, value)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Writes `content` to a file of the temporary directory, unique to the test.
+    fn file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("rake-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// Parses `cli` with `config` as the default configuration file.
+    fn parse(cli: &[&str], config: &Path) -> Result<ArgsSchema, String> {
+        let cli = ["rake"].iter().chain(cli).map(OsString::from).collect();
+        merge(cli, Some(config.to_path_buf())).map(ArgsSchema::parse_from)
+    }
+
+    #[test]
+    fn command_line_overrides_profile_overrides_file() {
+        let config = file(
+            "layers.toml",
+            "wordlist = \"words.txt\"\nthreads = 10\nrate = 50\n[profiles.slow]\nrate = 5\ntimeout = 9000\n",
+        );
+
+        let args = parse(&["-u", "http://target/{}"], &config).unwrap();
+        assert_eq!((args.threads, args.rate, args.timeout), (10, 50, 5000));
+
+        let args = parse(&["-u", "http://target/{}", "--profile", "slow", "-t", "20"], &config).unwrap();
+        assert_eq!(args.wordlist, "words.txt");
+        assert_eq!((args.threads, args.rate, args.timeout), (20, 5, 9000));
+    }
+
+    #[test]
+    fn url_and_url_list_override_each_other() {
+        let targets = file("targets.txt", "http://other/{}\n");
+        let config = file("url.toml", "wordlist = \"words.txt\"\nurl = \"http://target/{}\"\n");
+
+        let args = parse(&["-U", targets.to_str().unwrap()], &config).unwrap();
+        assert!(args.url.is_none());
+        assert!(args.target_list.is_some());
+
+        let args = parse(&[], &config).unwrap();
+        assert_eq!(args.url.as_deref(), Some("http://target/{}"));
+        assert!(args.target_list.is_none());
+    }
+
+    #[test]
+    fn flags_of_the_file_can_be_turned_off() {
+        let config = file("flags.toml", "wordlist = \"words.txt\"\ndedupe = true\nseed = true\n");
+
+        let args = parse(&["-u", "http://target/{}"], &config).unwrap();
+        assert!(args.dedupe && args.seed);
+
+        let args = parse(&["-u", "http://target/{}", "--no-dedupe"], &config).unwrap();
+        assert!(!args.dedupe && args.seed);
+    }
+
+    #[test]
+    fn reports_invalid_files() {
+        let error = |content: &str, cli: &[&str]| {
+            let config = file("invalid.toml", content);
+            parse(&[&["-u", "http://target/{}", "-w", "words.txt"], cli].concat(), &config).err().unwrap()
+        };
+
+        assert!(error("thread = 10", &[]).starts_with("Unknown option `thread`"));
+        assert!(error("config = \"other.toml\"", &[]).starts_with("`config` cannot be set"));
+        assert!(error("dedupe = \"yes\"", &[]).starts_with("`dedupe` is a flag"));
+        assert!(error("profiles = 1", &[]).starts_with("`profiles` must be a table"));
+        assert!(error("", &["--profile", "fast"]).starts_with("Profile `fast` not found"));
+
+        let cli = ["rake", "-u", "http://target/{}", "--profile", "fast"].map(OsString::from).to_vec();
+        let missing = env::temp_dir().join("rake-missing.toml");
+        assert!(merge(cli, Some(missing)).err().unwrap().contains("there is no configuration file"));
+    }
+}
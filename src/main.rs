@@ -1,12 +1,37 @@
-use args::ArgsSchema;
-use clap::Parser;
 use fuzzer::{FuzzerArgs, fuzzer::Fuzzer};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
 mod args;
+mod config;
+
+/// Exit code of a process stopped by SIGINT.
+const INTERRUPTED: i32 = 130;
 
 fn main() {
-    let args_schema = ArgsSchema::parse();
+    let args_schema = config::parse();
+
+    // Installed before the scan is set up, as preflight checks, calibration and seeding
+    // can take a while. The first signal lets the scan wind down and save a checkpoint,
+    // a second one exits at once.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    if let Err(e) = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            std::process::exit(INTERRUPTED);
+        }
+        eprintln!("\nStopping, waiting for in-flight requests. Press Ctrl-C again to exit immediately.");
+    }) {
+        eprintln!("Cannot handle interruptions: {}", e);
+    }
 
-    let mut fuzzer = Fuzzer::new(&FuzzerArgs::from(args_schema));
+    let mut fuzzer = Fuzzer::new(&FuzzerArgs::from(args_schema)).with_interrupt_flag(Arc::clone(&interrupted));
     fuzzer.fuzz();
+
+    // Scans stopped by a limit or from the console end normally.
+    if interrupted.load(Ordering::SeqCst) {
+        std::process::exit(INTERRUPTED);
+    }
 }